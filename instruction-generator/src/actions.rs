@@ -0,0 +1,405 @@
+use borsh::BorshDeserialize;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, system_instruction};
+use spl_governance::state::native_treasury::get_native_treasury_address;
+
+use std::str::FromStr;
+
+use proposal_actions::{
+    config, ActionInstruction, GrantInstruction, OptionActions, ProposalVoteType, RawInstruction,
+    TransferInstruction, DEFAULT_OPTION_LABEL,
+};
+
+use crate::{validate::GrantSource, Grant, LocalInstructionData, ProposalData};
+
+/// Proposal action as it's written in grants file, `type` field selects the kind:
+///
+/// ``` json
+/// { "type": "sol_transfer", "destination": "address", "lamports": 100000000 }
+/// ```
+///
+/// Accounts that aren't set default to the DAO accounts from config.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    Grant(Grant),
+    /// `amount` in base units, from `DEPOSIT_TOKEN` owned by `DEPOSIT_TOKEN_AUTH` by default
+    SplTransfer {
+        destination: String,
+        amount: u64,
+        #[serde(default)]
+        source: Option<String>,
+        #[serde(default)]
+        authority: Option<String>,
+        #[serde(default)]
+        group: Option<String>,
+    },
+    /// from the native treasury of `GOVERNANCE` by default
+    SolTransfer {
+        destination: String,
+        lamports: u64,
+        #[serde(default)]
+        source: Option<String>,
+        #[serde(default)]
+        group: Option<String>,
+    },
+    /// `amount` in base units of `MINT` by default, minted by `GOVERNANCE` by default
+    MintTo {
+        destination: String,
+        amount: u64,
+        #[serde(default)]
+        mint: Option<String>,
+        #[serde(default)]
+        authority: Option<String>,
+        #[serde(default)]
+        group: Option<String>,
+    },
+    /// base64 of borsh serialized instruction, the format Realms UI uses for custom instructions
+    RawInstruction {
+        instruction: String,
+        #[serde(default)]
+        group: Option<String>,
+    },
+    /// Grant moved to the grants list of the file, so it's validated together with other grants
+    #[serde(skip)]
+    GrantIndex(usize),
+}
+
+/// Compiled proposal, proposal without `options` has the single `Approve` one with `actions`
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ProposalActions {
+    pub name: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<ActionInstruction>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<OptionActions>,
+    pub vote_type: ProposalVoteType,
+}
+
+impl ProposalActions {
+    /// Options of the proposal, the single `Approve` one if there are no options
+    pub fn options(&self) -> Vec<OptionActions> {
+        if !self.options.is_empty() {
+            return self.options.clone();
+        }
+
+        vec![OptionActions {
+            label: DEFAULT_OPTION_LABEL.to_string(),
+            actions: self.actions.clone(),
+        }]
+    }
+}
+
+/// Moves `new_grants` and grant actions of the `option`, or of the file when it's `None`,
+/// to `grants` of the file, so validation, preview and amount resolution see them.
+/// Returns actions in insertion order, `new_grants` go first.
+pub fn lift_grants(
+    grants: &mut Vec<Grant>,
+    new_grants: Vec<Grant>,
+    actions: Vec<Action>,
+    option: Option<u8>,
+) -> Vec<Action> {
+    let mut ordered = Vec::new();
+
+    let new_grants = new_grants
+        .into_iter()
+        .map(Action::Grant)
+        .enumerate()
+        .map(|(index, grant)| (false, index, grant));
+    let actions = actions
+        .into_iter()
+        .enumerate()
+        .map(|(index, action)| (true, index, action));
+
+    for (is_action, index, action) in new_grants.chain(actions) {
+        match action {
+            Action::Grant(mut grant) => {
+                grant.option_index = option.unwrap_or(0);
+                // grants read from CSV keep their line
+                if !matches!(grant.source, GrantSource::CsvLine { .. }) {
+                    grant.source = GrantSource::Entry {
+                        option: option.map(|option| option as usize + 1),
+                        action: is_action,
+                        index: index + 1,
+                    };
+                }
+                grants.push(grant);
+                ordered.push(Action::GrantIndex(grants.len() - 1));
+            }
+            action => ordered.push(action),
+        }
+    }
+
+    ordered
+}
+
+fn parse_address(name: &str, value: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(value).map_err(|e| format!("invalid {} {}: {}", name, value, e))
+}
+
+fn address_or(
+    name: &str,
+    value: &Option<String>,
+    default: impl FnOnce() -> Pubkey,
+) -> Result<Pubkey, String> {
+    match value {
+        Some(value) => parse_address(name, value),
+        None => Ok(default()),
+    }
+}
+
+fn optional_address(name: &str, value: &Option<String>) -> Result<Option<Pubkey>, String> {
+    value
+        .as_ref()
+        .map(|value| parse_address(name, value))
+        .transpose()
+}
+
+/// Instruction of `raw_instruction` action, base64 of borsh serialized `LocalInstructionData`
+fn decode_raw_instruction(instruction: &str) -> Result<Instruction, String> {
+    let bytes = base64::decode(instruction)
+        .map_err(|e| format!("instruction is not valid base64: {}", e))?;
+
+    let instruction_data = LocalInstructionData::try_from_slice(&bytes)
+        .map_err(|e| format!("instruction can't be decoded: {}", e))?;
+
+    Ok(Instruction::from(&instruction_data))
+}
+
+/// Checks fields of the action without DAO accounts, so it works offline.
+/// Grants are checked together with the grants list of the file.
+fn check_action(action: &Action) -> Result<(), String> {
+    match action {
+        Action::SplTransfer {
+            destination,
+            source,
+            authority,
+            ..
+        } => {
+            parse_address("destination", destination)?;
+            optional_address("source", source)?;
+            optional_address("authority", authority)?;
+        }
+        Action::SolTransfer {
+            destination,
+            source,
+            ..
+        } => {
+            parse_address("destination", destination)?;
+            optional_address("source", source)?;
+        }
+        Action::MintTo {
+            destination,
+            mint,
+            authority,
+            ..
+        } => {
+            parse_address("destination", destination)?;
+            optional_address("mint", mint)?;
+            optional_address("authority", authority)?;
+        }
+        Action::RawInstruction { instruction, .. } => {
+            decode_raw_instruction(instruction)?;
+        }
+        Action::Grant(_) | Action::GrantIndex(_) => {}
+    }
+
+    Ok(())
+}
+
+/// Problems of the actions of the proposal and of each of its options, found without compiling
+pub fn validate_actions(data: &ProposalData<Grant>) -> Vec<String> {
+    let options = data
+        .options
+        .iter()
+        .enumerate()
+        .map(|(index, option)| (format!("option {} ", index + 1), &option.actions));
+
+    let mut errors = Vec::new();
+
+    for (context, actions) in std::iter::once((String::new(), &data.actions)).chain(options) {
+        for (index, action) in actions.iter().enumerate() {
+            if let Err(e) = check_action(action) {
+                errors.push(format!("{}action {}: {}", context, index + 1, e));
+            }
+        }
+    }
+
+    errors
+}
+
+/// Compiles the action, grants are taken from already compiled `grants`
+fn compile_action(
+    config: &config::Config,
+    action: &Action,
+    grants: &mut [Option<GrantInstruction>],
+) -> Result<ActionInstruction, String> {
+    match action {
+        Action::SplTransfer {
+            destination,
+            amount,
+            source,
+            authority,
+            group,
+        } => {
+            let destination = parse_address("destination", destination)?;
+            let source = address_or("source", source, || config.pubkey("DEPOSIT_TOKEN"))?;
+            let authority = address_or("authority", authority, || {
+                config.pubkey("DEPOSIT_TOKEN_AUTH")
+            })?;
+
+            let instruction = spl_token::instruction::transfer(
+                &spl_token::id(),
+                &source,
+                &destination,
+                &authority,
+                &[],
+                *amount,
+            )
+            .map_err(|e| e.to_string())?;
+
+            Ok(ActionInstruction::SplTransfer(TransferInstruction {
+                source: source.to_string(),
+                destination: destination.to_string(),
+                amount: *amount,
+                group: group.clone(),
+                instruction: bincode::serialize(&instruction).unwrap(),
+            }))
+        }
+        Action::SolTransfer {
+            destination,
+            lamports,
+            source,
+            group,
+        } => {
+            let destination = parse_address("destination", destination)?;
+            let source = address_or("source", source, || {
+                get_native_treasury_address(
+                    &config.pubkey("GOVERNANCE_PROGRAM"),
+                    &config.pubkey("GOVERNANCE"),
+                )
+            })?;
+
+            let instruction = system_instruction::transfer(&source, &destination, *lamports);
+
+            Ok(ActionInstruction::SolTransfer(TransferInstruction {
+                source: source.to_string(),
+                destination: destination.to_string(),
+                amount: *lamports,
+                group: group.clone(),
+                instruction: bincode::serialize(&instruction).unwrap(),
+            }))
+        }
+        Action::MintTo {
+            destination,
+            amount,
+            mint,
+            authority,
+            group,
+        } => {
+            let destination = parse_address("destination", destination)?;
+            let mint = address_or("mint", mint, || config.pubkey("MINT"))?;
+            let authority = address_or("authority", authority, || config.pubkey("GOVERNANCE"))?;
+
+            let instruction = spl_token::instruction::mint_to(
+                &spl_token::id(),
+                &mint,
+                &destination,
+                &authority,
+                &[],
+                *amount,
+            )
+            .map_err(|e| e.to_string())?;
+
+            Ok(ActionInstruction::MintTo(TransferInstruction {
+                source: mint.to_string(),
+                destination: destination.to_string(),
+                amount: *amount,
+                group: group.clone(),
+                instruction: bincode::serialize(&instruction).unwrap(),
+            }))
+        }
+        Action::RawInstruction { instruction, group } => {
+            let instruction = decode_raw_instruction(instruction)?;
+
+            Ok(ActionInstruction::RawInstruction(RawInstruction {
+                program_id: instruction.program_id.to_string(),
+                group: group.clone(),
+                instruction: bincode::serialize(&instruction).unwrap(),
+            }))
+        }
+        Action::GrantIndex(grant_index) => grants
+            .get_mut(*grant_index)
+            .and_then(Option::take)
+            .map(ActionInstruction::Grant)
+            .ok_or_else(|| format!("grant {} is not compiled", grant_index + 1)),
+        // grants are moved to the grants list of the file by `lift_grants` before compiling
+        Action::Grant(grant) => Err(format!(
+            "grant to {} is not moved to the grants list",
+            grant.wallet
+        )),
+    }
+}
+
+/// Compiles actions in their order, errors are prefixed with `context`
+fn action_instructions(
+    config: &config::Config,
+    actions: &[Action],
+    grants: &mut [Option<GrantInstruction>],
+    context: &str,
+    errors: &mut Vec<String>,
+) -> Vec<ActionInstruction> {
+    let mut instructions = Vec::new();
+
+    for (index, action) in actions.iter().enumerate() {
+        match compile_action(config, action, grants) {
+            Ok(instruction) => instructions.push(instruction),
+            Err(e) => errors.push(format!("{}action {}: {}", context, index + 1, e)),
+        }
+    }
+
+    instructions
+}
+
+/// Compiles actions of the proposal or of each of its options, `grants` are compiled `data.grants`.
+/// Returns all the problems found if there are any.
+pub fn proposal_actions(
+    config: &config::Config,
+    data: &ProposalData<Grant>,
+    grants: Vec<GrantInstruction>,
+) -> Result<ProposalActions, Vec<String>> {
+    let mut grants: Vec<Option<GrantInstruction>> = grants.into_iter().map(Some).collect();
+
+    let mut errors = Vec::new();
+
+    let actions = action_instructions(config, &data.actions, &mut grants, "", &mut errors);
+
+    let options = data
+        .options
+        .iter()
+        .enumerate()
+        .map(|(index, option)| OptionActions {
+            label: option.label.clone(),
+            actions: action_instructions(
+                config,
+                &option.actions,
+                &mut grants,
+                &format!("option {} ", index + 1),
+                &mut errors,
+            ),
+        })
+        .collect();
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(ProposalActions {
+        name: data.name.clone(),
+        description: data.description.clone(),
+        actions,
+        options,
+        vote_type: data.vote_type,
+    })
+}
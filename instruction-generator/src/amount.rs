@@ -0,0 +1,199 @@
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
+
+use crate::{validate::GrantIssue, Grant};
+
+/// Converts decimal token amount like `12500.5` to the base units of a mint with given decimals.
+/// Amounts with more fractional digits than mint supports are rejected instead of being rounded.
+pub fn ui_amount_to_amount(ui_amount: &str, decimals: u8) -> Result<u64, String> {
+    let ui_amount = ui_amount.trim();
+
+    let (integer, fraction) = match ui_amount.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (ui_amount, None),
+    };
+
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+
+    // `12500.` is most likely a typo, so the dot has to be followed by digits
+    let fraction_is_valid = fraction
+        .map(|fraction| !fraction.is_empty() && is_digits(fraction))
+        .unwrap_or(true);
+
+    if integer.is_empty() || !is_digits(integer) || !fraction_is_valid {
+        return Err(format!("`{}` is not a valid decimal amount", ui_amount));
+    }
+
+    let fraction = fraction.unwrap_or("").trim_end_matches('0');
+
+    if fraction.len() > decimals as usize {
+        return Err(format!(
+            "`{}` has more fractional digits than mint supports({})",
+            ui_amount, decimals
+        ));
+    }
+
+    let too_big = || format!("`{}` is too big", ui_amount);
+
+    let integer = integer.parse::<u64>().map_err(|_| too_big())?;
+    let fraction = format!("{:0<width$}", fraction, width = decimals as usize);
+    let fraction = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<u64>().map_err(|_| too_big())?
+    };
+
+    10_u64
+        .checked_pow(decimals as u32)
+        .and_then(|multiplier| integer.checked_mul(multiplier))
+        .and_then(|amount| amount.checked_add(fraction))
+        .ok_or_else(too_big)
+}
+
+/// Formats base units amount as decimal token amount, opposite to `ui_amount_to_amount`
+pub fn amount_to_ui_amount(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+
+    let amount = format!("{:0>width$}", amount, width = decimals as usize + 1);
+    let (integer, fraction) = amount.split_at(amount.len() - decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+pub fn fetch_mint_decimals(client: &RpcClient, mint: &Pubkey) -> Result<u8, String> {
+    let mint_data = client
+        .get_account_data(mint)
+        .map_err(|e| format!("Failed to load mint {}: {}", mint, e))?;
+
+    spl_token::state::Mint::unpack(&mint_data)
+        .map(|mint| mint.decimals)
+        .map_err(|e| format!("Account {} is not a token mint: {}", mint, e))
+}
+
+/// Fills in `amount` of the grants that have `ui_amount` set
+pub fn resolve_ui_amounts(grants: &mut [Grant], decimals: u8) -> Vec<GrantIssue> {
+    let mut issues = Vec::new();
+
+    for grant in grants.iter_mut() {
+        let ui_amount = match &grant.ui_amount {
+            Some(ui_amount) => ui_amount,
+            None => continue,
+        };
+
+        if grant.amount.is_some() {
+            issues.push(GrantIssue::new(
+                grant,
+                Some("ui_amount"),
+                "both amount and ui_amount are set, only one should be used".to_string(),
+            ));
+            continue;
+        }
+
+        match ui_amount_to_amount(ui_amount, decimals) {
+            Ok(amount) => grant.amount = Some(amount),
+            Err(message) => issues.push(GrantIssue::new(grant, Some("ui_amount"), message)),
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    #[test]
+    fn converts_ui_amount() {
+        assert_eq!(ui_amount_to_amount("12500", 6), Ok(12_500_000_000));
+        assert_eq!(ui_amount_to_amount("12500.5", 6), Ok(12_500_500_000));
+        assert_eq!(ui_amount_to_amount(" 0.000001 ", 6), Ok(1));
+        assert_eq!(ui_amount_to_amount("1.50", 1), Ok(15));
+        assert_eq!(ui_amount_to_amount("7", 0), Ok(7));
+    }
+
+    #[test]
+    fn rejects_malformed_ui_amount() {
+        for ui_amount in ["12500.", ".5", "", "1.2.3", "-1", "1e6", "1,5"] {
+            assert!(
+                ui_amount_to_amount(ui_amount, 6).is_err(),
+                "`{}` is accepted",
+                ui_amount
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_too_many_fractional_digits() {
+        assert!(ui_amount_to_amount("0.0000001", 6).is_err());
+        assert!(ui_amount_to_amount("1.5", 0).is_err());
+        // trailing zeros don't count
+        assert_eq!(ui_amount_to_amount("1.5000000", 6), Ok(1_500_000));
+    }
+
+    #[test]
+    fn rejects_overflowing_ui_amount() {
+        assert_eq!(ui_amount_to_amount("18446744073709551615", 0), Ok(u64::MAX));
+        assert!(ui_amount_to_amount("18446744073709551616", 0).is_err());
+        // fits into u64 only without decimals
+        assert_eq!(
+            ui_amount_to_amount("18446744073", 9),
+            Ok(18_446_744_073_000_000_000)
+        );
+        assert!(ui_amount_to_amount("18446744074", 9).is_err());
+        assert!(ui_amount_to_amount("18446744073.709551616", 9).is_err());
+        // 10^20 doesn't fit into u64
+        assert!(ui_amount_to_amount("1", 20).is_err());
+    }
+
+    #[test]
+    fn formats_amount() {
+        assert_eq!(amount_to_ui_amount(1, 6), "0.000001");
+        assert_eq!(amount_to_ui_amount(12_500_500_000, 6), "12500.5");
+        assert_eq!(amount_to_ui_amount(12_500_000_000, 6), "12500");
+        assert_eq!(amount_to_ui_amount(0, 6), "0");
+        assert_eq!(amount_to_ui_amount(7, 0), "7");
+        assert_eq!(amount_to_ui_amount(u64::MAX, 9), "18446744073.709551615");
+    }
+
+    #[test]
+    fn formatted_amount_converts_back() {
+        for (amount, decimals) in [(1, 6), (12_500_500_000, 6), (u64::MAX, 9), (42, 0)] {
+            let ui_amount = amount_to_ui_amount(amount, decimals);
+
+            assert_eq!(ui_amount_to_amount(&ui_amount, decimals), Ok(amount));
+        }
+    }
+
+    #[test]
+    fn resolves_ui_amounts() {
+        let mut grants: Vec<Grant> = serde_json::from_value(json!([
+            { "wallet": "a", "grant_type": "None", "start": null, "periods": 0,
+              "allow_clawback": false, "ui_amount": "1.5" },
+            { "wallet": "b", "grant_type": "None", "start": null, "periods": 0,
+              "allow_clawback": false, "amount": 7 },
+            { "wallet": "c", "grant_type": "None", "start": null, "periods": 0,
+              "allow_clawback": false, "amount": 7, "ui_amount": "7" },
+            { "wallet": "d", "grant_type": "None", "start": null, "periods": 0,
+              "allow_clawback": false, "ui_amount": "0.0000001" },
+        ]))
+        .unwrap();
+
+        let issues = resolve_ui_amounts(&mut grants, 6);
+
+        assert_eq!(grants[0].amount, Some(1_500_000));
+        assert_eq!(grants[1].amount, Some(7));
+
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+        assert!(issues[0].message.contains("both amount and ui_amount"));
+        assert!(issues[1].message.contains("0.0000001"));
+    }
+}
@@ -0,0 +1,275 @@
+use proposal_actions::{hex, transaction_size, ActionInstruction, ProposalVoteType};
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+use spl_governance::{
+    instruction::{add_signatory, create_proposal},
+    state::proposal::{get_proposal_address, VoteType},
+};
+
+use crate::{actions::ProposalActions, amount, start_time};
+
+/// Size of the transaction creating the proposal with `description`, the way proposal-creator
+/// sends it: `CreateProposal` together with `AddSignatory`. Accounts are placeholders, the size
+/// depends only on their number, signatory is counted as a separate account.
+pub fn creation_transaction_size(data: &ProposalActions, description: &str) -> usize {
+    let governance_program = Pubkey::new_unique();
+    let governance = Pubkey::new_unique();
+    let realm = Pubkey::new_unique();
+    let governing_token_mint = Pubkey::new_unique();
+    let proposal_owner_record = Pubkey::new_unique();
+    let payer = Pubkey::new_unique();
+    let signatory = Pubkey::new_unique();
+
+    let options: Vec<String> = data
+        .options()
+        .into_iter()
+        .map(|option| option.label)
+        .collect();
+
+    let vote_type = match data.vote_type {
+        ProposalVoteType::SingleChoice => VoteType::SingleChoice,
+        ProposalVoteType::MultiChoice => VoteType::MultiChoice {
+            max_voter_options: options.len() as u8,
+            max_winning_options: options.len() as u8,
+        },
+    };
+
+    let create_proposal = create_proposal(
+        &governance_program,
+        &governance,
+        &proposal_owner_record,
+        &payer,
+        &payer,
+        None,
+        &realm,
+        data.name.clone(),
+        description.to_string(),
+        &governing_token_mint,
+        vote_type,
+        options,
+        data.vote_type == ProposalVoteType::SingleChoice,
+        0,
+    );
+
+    let proposal = get_proposal_address(
+        &governance_program,
+        &governance,
+        &governing_token_mint,
+        &0u32.to_le_bytes(),
+    );
+
+    let add_signatory = add_signatory(
+        &governance_program,
+        &proposal,
+        &proposal_owner_record,
+        &payer,
+        &payer,
+        &signatory,
+    );
+
+    transaction_size(&payer, &[create_proposal, add_signatory])
+}
+
+fn format_amount(amount: u64, decimals: u8) -> String {
+    amount::amount_to_ui_amount(amount, decimals)
+}
+
+/// Markdown document with a table of grants and the list of other actions of every option,
+/// totals and the hash of the instructions. `intro` goes before everything else if not empty.
+pub fn markdown(data: &ProposalActions, intro: &str, decimals: u8, hash: &Hash) -> String {
+    let mut doc = String::new();
+
+    if !intro.is_empty() {
+        doc.push_str(intro);
+        doc.push_str("\n\n");
+    }
+
+    let options = data.options();
+
+    for option in options.iter() {
+        if options.len() > 1 {
+            doc.push_str(&format!("## {}\n\n", option.label));
+        }
+
+        let grants: Vec<_> = option
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                ActionInstruction::Grant(grant) => Some(grant),
+                _ => None,
+            })
+            .collect();
+
+        if !grants.is_empty() {
+            doc.push_str("| Wallet | Type | Start (UTC) | Periods | Amount | Clawback |\n");
+            doc.push_str("|---|---|---|---|---:|---|\n");
+
+            for grant in grants.iter() {
+                doc.push_str(&format!(
+                    "| {} | {:?} | {} | {} | {} | {} |\n",
+                    grant.wallet,
+                    grant.grant_type,
+                    grant
+                        .start
+                        .map(start_time::format_utc)
+                        .unwrap_or_else(|| "at execution".to_string()),
+                    grant.periods,
+                    format_amount(grant.amount, decimals),
+                    if grant.allow_clawback { "yes" } else { "no" }
+                ));
+            }
+
+            let mut wallets: Vec<&String> = grants.iter().map(|grant| &grant.wallet).collect();
+            wallets.sort();
+            wallets.dedup();
+
+            doc.push_str(&format!(
+                "\nTotal: {} grant(s) to {} wallet(s), {} tokens\n\n",
+                grants.len(),
+                wallets.len(),
+                format_amount(
+                    grants
+                        .iter()
+                        .map(|grant| grant.amount)
+                        .fold(0, u64::saturating_add),
+                    decimals
+                )
+            ));
+        }
+
+        let other_actions: Vec<_> = option
+            .actions
+            .iter()
+            .filter(|action| !matches!(action, ActionInstruction::Grant(_)))
+            .collect();
+
+        if !other_actions.is_empty() {
+            doc.push_str("Other actions:\n\n");
+
+            for action in other_actions.iter() {
+                doc.push_str(&format!("- {}\n", action.describe()));
+            }
+
+            doc.push('\n');
+        }
+    }
+
+    doc.push_str(&format!("SHA-256 of the instructions: `{}`\n", hex(hash)));
+
+    doc
+}
+
+/// Short on-chain description pointing to the full document
+pub fn link_description(link: &str, hash: &Hash) -> String {
+    format!("{}\n\nSHA-256 of the instructions: {}", link, hex(hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proposal_actions::{
+        GrantInstruction, GrantType, OptionActions, ProposalVoteType, TransferInstruction,
+        DEFAULT_OPTION_LABEL,
+    };
+
+    fn grant(wallet: &str, amount: u64) -> ActionInstruction {
+        ActionInstruction::Grant(GrantInstruction {
+            wallet: wallet.to_string(),
+            grant_type: GrantType::Monthly,
+            start: Some(1_767_225_600),
+            periods: 12,
+            allow_clawback: true,
+            amount,
+            group: None,
+            instruction: Vec::new(),
+        })
+    }
+
+    fn sol_transfer() -> ActionInstruction {
+        ActionInstruction::SolTransfer(TransferInstruction {
+            source: "treasury".to_string(),
+            destination: "wallet".to_string(),
+            amount: 5,
+            group: None,
+            instruction: Vec::new(),
+        })
+    }
+
+    fn proposal(actions: Vec<ActionInstruction>, options: Vec<OptionActions>) -> ProposalActions {
+        ProposalActions {
+            name: "Proposal".to_string(),
+            description: String::new(),
+            actions,
+            options,
+            vote_type: ProposalVoteType::SingleChoice,
+        }
+    }
+
+    #[test]
+    fn describes_single_option() {
+        let data = proposal(
+            vec![grant("a", 1_500_000), grant("a", 500_000), sol_transfer()],
+            Vec::new(),
+        );
+
+        let doc = markdown(&data, "Intro", 6, &Hash::default());
+
+        assert_eq!(
+            doc,
+            "Intro\n\
+             \n\
+             | Wallet | Type | Start (UTC) | Periods | Amount | Clawback |\n\
+             |---|---|---|---|---:|---|\n\
+             | a | Monthly | 2026-01-01T00:00:00Z | 12 | 1.5 | yes |\n\
+             | a | Monthly | 2026-01-01T00:00:00Z | 12 | 0.5 | yes |\n\
+             \n\
+             Total: 2 grant(s) to 1 wallet(s), 2 tokens\n\
+             \n\
+             Other actions:\n\
+             \n\
+             - sol transfer of 5 lamports from treasury to wallet\n\
+             \n\
+             SHA-256 of the instructions: `0000000000000000000000000000000000000000000000000000000000000000`\n"
+        );
+    }
+
+    #[test]
+    fn describes_every_option() {
+        let data = proposal(
+            Vec::new(),
+            vec![
+                OptionActions {
+                    label: "First".to_string(),
+                    actions: vec![grant("a", 1_000_000), grant("b", 1_000_000)],
+                },
+                OptionActions {
+                    label: "Second".to_string(),
+                    actions: vec![sol_transfer()],
+                },
+            ],
+        );
+
+        let doc = markdown(&data, "", 6, &Hash::default());
+
+        let first = doc.find("## First\n").unwrap();
+        let total = doc
+            .find("Total: 2 grant(s) to 2 wallet(s), 2 tokens")
+            .unwrap();
+        let second = doc.find("## Second\n").unwrap();
+        let other = doc.find("Other actions:").unwrap();
+
+        assert!(first < total && total < second && second < other);
+        assert_eq!(doc.matches("Total:").count(), 1);
+        assert!(doc.ends_with("SHA-256 of the instructions: `0000000000000000000000000000000000000000000000000000000000000000`\n"));
+    }
+
+    #[test]
+    fn omits_heading_of_the_only_option() {
+        let data = proposal(vec![grant("a", 1)], Vec::new());
+
+        let doc = markdown(&data, "", 0, &Hash::default());
+
+        assert!(doc.starts_with("| Wallet |"));
+        assert!(!doc.contains(DEFAULT_OPTION_LABEL));
+    }
+}
@@ -0,0 +1,275 @@
+use csv::{ReaderBuilder, StringRecord, Trim};
+use serde::{
+    de::{value, IntoDeserializer},
+    Deserialize,
+};
+
+use proposal_actions::{GrantType, ProposalVoteType};
+
+use crate::{validate::GrantSource, Grant, ProposalData};
+
+/// Parses grants from CSV with columns matching `Grant` struct:
+///
+/// ``` text
+/// # name: Proposal name
+/// # description: Proposal description
+/// wallet,grant_type,start,periods,allow_clawback,amount,group
+/// address,Monthly,,12,true,1000000,advisor
+/// ```
+///
+/// `#` lines before the header are optional, proposal name and description may be passed as flags instead.
+/// Every error points at the line of the file and the column it happened in,
+/// grants keep their line so problems found later point at it as well.
+pub fn parse_grants_csv(data: &str) -> Result<ProposalData<Grant>, Vec<String>> {
+    let mut name = String::new();
+    let mut description = String::new();
+
+    let mut header_lines = 0;
+
+    for line in data.lines() {
+        let line = line.trim();
+
+        if !line.starts_with('#') && !line.is_empty() {
+            break;
+        }
+
+        header_lines += 1;
+
+        if let Some((key, value)) = line.trim_start_matches('#').split_once(':') {
+            match key.trim() {
+                "name" => name = value.trim().to_string(),
+                "description" => description = value.trim().to_string(),
+                _ => {}
+            }
+        }
+    }
+
+    let body = data
+        .lines()
+        .skip(header_lines)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut reader = ReaderBuilder::new()
+        .trim(Trim::All)
+        .from_reader(body.as_bytes());
+
+    let mut errors = Vec::new();
+
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => return Err(vec![csv_error(&e, header_lines, None)]),
+    };
+
+    let mut grants = Vec::new();
+
+    let columns: Vec<String> = headers.iter().map(ToString::to_string).collect();
+
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(csv_error(&e, header_lines, Some(&headers)));
+                continue;
+            }
+        };
+
+        match record.deserialize::<Grant>(Some(&headers)) {
+            Ok(mut grant) => {
+                grant.source = GrantSource::CsvLine {
+                    line: record_line(&record, header_lines),
+                    columns: columns.clone(),
+                };
+                grants.push(grant);
+            }
+            Err(e) => errors.push(
+                grant_type_error(&record, &headers, header_lines)
+                    .unwrap_or_else(|| csv_error(&e, header_lines, Some(&headers))),
+            ),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(ProposalData {
+        name,
+        description,
+        grants,
+        actions: Vec::new(),
+        options: Vec::new(),
+        vote_type: ProposalVoteType::SingleChoice,
+    })
+}
+
+/// csv doesn't report column for enum errors, so grant type is checked separately to point at it
+fn grant_type_error(
+    record: &StringRecord,
+    headers: &StringRecord,
+    header_lines: usize,
+) -> Option<String> {
+    let field = headers.iter().position(|header| header == "grant_type")?;
+    let grant_type = record.get(field)?;
+
+    let err = GrantType::deserialize(IntoDeserializer::<value::Error>::into_deserializer(
+        grant_type,
+    ))
+    .err()?;
+
+    Some(format!(
+        "line {}, column {} `grant_type`: {}",
+        record_line(record, header_lines),
+        field + 1,
+        err
+    ))
+}
+
+/// Line of the file the record starts at, `#` lines before the header included
+fn record_line(record: &StringRecord, header_lines: usize) -> usize {
+    record
+        .position()
+        .map(|position| position.line() as usize + header_lines)
+        .unwrap_or(header_lines + 1)
+}
+
+fn csv_error(error: &csv::Error, header_lines: usize, headers: Option<&StringRecord>) -> String {
+    let line = error
+        .position()
+        .map(|position| position.line() as usize + header_lines)
+        .unwrap_or(header_lines + 1);
+
+    match error.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => match err.field() {
+            Some(field) => {
+                let column = headers
+                    .and_then(|headers| headers.get(field as usize))
+                    .unwrap_or("");
+
+                format!(
+                    "line {}, column {} `{}`: {}",
+                    line,
+                    field + 1,
+                    column,
+                    err.kind()
+                )
+            }
+            None => format!("line {}: {}", line, err.kind()),
+        },
+        csv::ErrorKind::UnequalLengths {
+            expected_len, len, ..
+        } => format!(
+            "line {}: expected {} columns, found {}",
+            line, expected_len, len
+        ),
+        _ => format!("line {}: {}", line, error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{validate, GrantStart};
+
+    #[test]
+    fn parses_header_and_grants() {
+        let data = "\
+# name: Advisors
+# description: Grants to the advisors
+
+wallet,grant_type,start,periods,allow_clawback,amount,group
+11111111111111111111111111111111, Monthly ,,12,true,1000000,advisor
+11111111111111111111111111111111,Cliff,2026-01-01,365,false,500,
+";
+
+        let data = parse_grants_csv(data).unwrap();
+
+        assert_eq!(data.name, "Advisors");
+        assert_eq!(data.description, "Grants to the advisors");
+        assert_eq!(data.grants.len(), 2);
+
+        let first = &data.grants[0];
+        assert!(matches!(first.grant_type, GrantType::Monthly));
+        assert!(first.start.is_none());
+        assert_eq!(first.periods, 12);
+        assert!(first.allow_clawback);
+        assert_eq!(first.amount, Some(1_000_000));
+        assert_eq!(first.group.as_deref(), Some("advisor"));
+
+        let second = &data.grants[1];
+        assert!(matches!(second.grant_type, GrantType::Cliff));
+        assert!(matches!(&second.start, Some(GrantStart::Date(date)) if date == "2026-01-01"));
+        assert!(!second.allow_clawback);
+        assert_eq!(second.group, None);
+    }
+
+    #[test]
+    fn parses_without_header_lines() {
+        let data = "wallet,grant_type,start,periods,allow_clawback,ui_amount\n\
+                    11111111111111111111111111111111,None,,0,false,12.5\n";
+
+        let data = parse_grants_csv(data).unwrap();
+
+        assert!(data.name.is_empty());
+        assert!(data.description.is_empty());
+        assert_eq!(data.grants.len(), 1);
+        assert_eq!(data.grants[0].amount, None);
+        assert_eq!(data.grants[0].ui_amount.as_deref(), Some("12.5"));
+    }
+
+    #[test]
+    fn points_at_line_and_column() {
+        let data = "\
+# name: Advisors
+wallet,grant_type,start,periods,allow_clawback,amount
+11111111111111111111111111111111,Monthly,,12,true,1000000
+11111111111111111111111111111111,Weekly,,12,true,1000000
+11111111111111111111111111111111,Monthly,,twelve,true,1000000
+11111111111111111111111111111111,Monthly,,12
+";
+
+        let errors = parse_grants_csv(data).unwrap_err();
+
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(
+            errors[0].starts_with("line 4, column 2 `grant_type`"),
+            "{}",
+            errors[0]
+        );
+        assert!(
+            errors[1].starts_with("line 5, column 4 `periods`"),
+            "{}",
+            errors[1]
+        );
+        assert_eq!(errors[2], "line 6: expected 6 columns, found 4");
+    }
+
+    #[test]
+    fn grants_keep_their_line() {
+        let data = "\
+# name: Advisors
+wallet,grant_type,start,periods,allow_clawback,amount
+11111111111111111111111111111111,Monthly,,12,true,0
+not an address,Monthly,,12,true,1000000
+11111111111111111111111111111111,Monthly,,12,true,0
+";
+
+        let data = parse_grants_csv(data).unwrap();
+
+        let issues: Vec<_> = validate::validate_grants(&data.grants, 0)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        assert_eq!(issues.len(), 4, "{:?}", issues);
+        assert_eq!(issues[0], "line 3, column 6 `amount`: amount is zero");
+        assert!(
+            issues[1].starts_with("line 4, column 1 `wallet`: wallet `not an address`"),
+            "{}",
+            issues[1]
+        );
+        assert_eq!(issues[2], "line 5, column 6 `amount`: amount is zero");
+        assert_eq!(issues[3], "line 5: duplicate of line 3");
+    }
+}
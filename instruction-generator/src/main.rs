@@ -1,14 +1,20 @@
 use anchor_lang::InstructionData;
 use anchor_lang::ToAccountMetas;
 use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
-use clap::{arg, command, value_parser, ArgAction, Command};
+use clap::{arg, command, value_parser, ArgAction, ArgMatches, Command};
+use proposal_actions::{
+    config, hex, instructions_hash, ActionInstruction, GrantInstruction, GrantType,
+    ProposalVoteType,
+};
 use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
 use solana_remote_wallet::locator::Locator;
 use solana_remote_wallet::remote_keypair::generate_remote_keypair;
 use solana_remote_wallet::remote_wallet::maybe_wallet_manager;
 use solana_sdk::{
     derivation_path::DerivationPath,
     instruction::{AccountMeta, Instruction},
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
     signature::Signer,
     signer::keypair::read_keypair_file,
@@ -16,16 +22,26 @@ use solana_sdk::{
     sysvar::rent,
 };
 use uriparse::URIReference;
-use voter_stake_registry::state::LockupKind;
+use voter_stake_registry::state::{LockupKind, Registrar};
 
 use dotenv::dotenv;
 
 use std::{
-    env, fs,
+    fs,
     path::{Path, PathBuf},
+    process,
     str::FromStr,
 };
 
+mod actions;
+mod amount;
+mod description;
+mod grants_csv;
+mod start_time;
+mod validate;
+mod vesting;
+mod voter;
+
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, BorshSchema)]
 #[repr(C)]
 pub struct LocalInstructionData {
@@ -84,59 +100,113 @@ impl From<&LocalInstructionData> for Instruction {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum GrantType {
-    None,
-    Daily,
-    Monthly,
-    Cliff,
-    Constant,
+pub fn lockup_kind_of(grant_type: &GrantType) -> LockupKind {
+    match grant_type {
+        GrantType::None => LockupKind::None,
+        GrantType::Daily => LockupKind::Daily,
+        GrantType::Monthly => LockupKind::Monthly,
+        GrantType::Cliff => LockupKind::Cliff,
+        GrantType::Constant => LockupKind::Constant,
+    }
 }
 
-impl From<&GrantType> for LockupKind {
-    fn from(lockup: &GrantType) -> Self {
-        match lockup {
-            GrantType::None => LockupKind::None,
-            GrantType::Daily => LockupKind::Daily,
-            GrantType::Monthly => LockupKind::Monthly,
-            GrantType::Cliff => LockupKind::Cliff,
-            GrantType::Constant => LockupKind::Constant,
-        }
+pub fn grant_type_of(lockup: LockupKind) -> GrantType {
+    match lockup {
+        LockupKind::None => GrantType::None,
+        LockupKind::Daily => GrantType::Daily,
+        LockupKind::Monthly => GrantType::Monthly,
+        LockupKind::Cliff => GrantType::Cliff,
+        LockupKind::Constant => GrantType::Constant,
     }
 }
 
+/// Grant start as it's written in grants file, see `start_time::parse_start` for supported formats
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum GrantStart {
+    Timestamp(u64),
+    Date(String),
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Grant {
     pub wallet: String,
     pub grant_type: GrantType,
-    pub start: Option<u64>,
+    pub start: Option<GrantStart>,
     pub periods: u32,
     pub allow_clawback: bool,
-    pub amount: u64,
+    /// amount in base units of the mint
+    #[serde(default)]
+    pub amount: Option<u64>,
+    /// decimal amount in whole tokens, converted to `amount` using mint decimals
+    #[serde(default)]
+    pub ui_amount: Option<String>,
+    /// grants with the same group are executed together in one proposal transaction
+    #[serde(default)]
+    pub group: Option<String>,
+    /// proposal option the grant belongs to, set when the file is loaded
+    #[serde(skip)]
+    pub option_index: u8,
+    /// where the grant is written in the file, set when the file is loaded
+    #[serde(skip)]
+    pub source: validate::GrantSource,
+}
+
+impl Grant {
+    /// Start timestamp, `None` if it isn't set or isn't resolved yet
+    pub fn start_ts(&self) -> Option<u64> {
+        match self.start {
+            Some(GrantStart::Timestamp(timestamp)) => Some(timestamp),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProposalData<T: Serialize> {
     pub name: String,
     pub description: String,
+    #[serde(default = "Vec::new")]
     pub grants: Vec<T>,
+    /// other proposal actions, inserted after `grants` in the declared order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<actions::Action>,
+    /// options to choose between, each with its own grants and actions,
+    /// top level `grants` and `actions` go to the single `Approve` option when it's empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<ProposalOption>,
+    #[serde(default)]
+    pub vote_type: ProposalVoteType,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProposalOption {
+    pub label: String,
+    #[serde(default)]
+    pub grants: Vec<Grant>,
+    #[serde(default)]
+    pub actions: Vec<actions::Action>,
 }
 
+/// Clawback of one deposit entry, shaped as `GrantInstruction` so proposal-creator takes it as is.
+/// Grant fields describe the deposit, `amount` is what's still locked and returns to the DAO.
 #[derive(Serialize, Deserialize, Debug)]
-pub struct GrantInstruction {
+pub struct ClawbackInstruction {
     pub wallet: String,
     pub grant_type: GrantType,
     pub start: Option<u64>,
     pub periods: u32,
     pub allow_clawback: bool,
     pub amount: u64,
+    pub group: Option<String>,
+    pub deposit_entry_index: u8,
     pub instruction: Vec<u8>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WithdrawInstruction {
     pub wallet: String,
-    pub instruction: Vec<u8>,
+    pub instructions: Vec<Vec<u8>>,
 }
 
 fn main() {
@@ -145,66 +215,347 @@ fn main() {
     let matches = command!()
         .arg(
             arg!(
-                -w --wallet <FILE> "Fee payer wallet"
+                -w --wallet [FILE] "Fee payer wallet"
             )
             .value_parser(value_parser!(PathBuf)),
         )
+        .arg(
+            arg!(
+                -n --node [URL] "Solana RPC node URL"
+            )
+            .default_value("https://api.mainnet-beta.solana.com/"),
+        )
+        .arg(arg!(--config [FILE] "TOML config with DAO profiles").default_value("../config.toml"))
+        .arg(arg!(--profile [NAME] "DAO profile from config, env vars are used if not set"))
         .subcommand(
-            Command::new("grant")
+            grants_file_args(Command::new("grant"))
                 .about("creates new DAO proposal and attaches bunch of Grant transactions to it")
                 .arg(
-                    arg!(-g --grants "lists of grants to be created")
-                        .required(true)
-                        .action(ArgAction::Set),
+                    arg!(--"group-by-wallet" "put grants without group into one proposal transaction per wallet")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--"skip-registrar-check" "don't check that the mint is configured in the registrar")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--"generate-description" "generate proposal description from the grants")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--"description-link" [URL] "where the generated description is published, only the link and hash go on-chain")
+                        .requires("generate-description"),
                 ),
         )
+        .subcommand(
+            grants_file_args(Command::new("validate"))
+                .about("checks grants file and reports every problem found"),
+        )
+        .subcommand(
+            grants_file_args(Command::new("preview"))
+                .about("shows when tokens of every grant are unlocked"),
+        )
+        .subcommand(
+            Command::new("clawback")
+                .about("creates DAO proposal that claws back locked tokens of the grantees")
+                .arg(arg!(<WALLET> ... "grantee wallets"))
+                .arg(arg!(--name [NAME] "proposal name"))
+                .arg(arg!(--description [DESCRIPTION] "proposal description")),
+        )
+        .subcommand(
+            Command::new("inspect-voter")
+                .about("shows deposits of the grantee and when they unlock")
+                .arg(arg!(<WALLET> "grantee wallet"))
+                .arg(arg!(--json "print as JSON").action(ArgAction::SetTrue)),
+        )
         .subcommand(
             Command::new("withdraw")
                 .about("withdraw locked tokens")
                 .arg(
                     arg!(-d --deposit "deposit index")
-                        .required(true)
+                        .required_unless_present("all")
                         .value_parser(clap::value_parser!(u8))
                         .action(ArgAction::Set),
                 )
                 .arg(
                     arg!(-a --amount "amount to withdraw")
-                        .required(true)
+                        .required_unless_present("all")
                         .value_parser(clap::value_parser!(u64))
                         .action(ArgAction::Set),
+                )
+                .arg(
+                    arg!(--all "withdraw everything unlocked from all the deposits")
+                        .conflicts_with_all(&["deposit", "amount"])
+                        .action(ArgAction::SetTrue),
                 ),
         )
         .get_matches();
 
-    let wallet_path = matches.get_one::<PathBuf>("wallet").unwrap();
+    let wallet_path = matches.get_one::<PathBuf>("wallet");
 
-    let signer = keypair_or_ledger_of(wallet_path);
+    let node_url = matches.get_one::<String>("node").unwrap();
+
+    let client = RpcClient::new(node_url);
+
+    let config = config::Config::load(
+        matches.get_one::<String>("config").unwrap(),
+        matches
+            .get_one::<String>("profile")
+            .map(|profile| profile.as_str()),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Config error: {}", e);
+        process::exit(1);
+    });
+
+    if let Some(profile) = config.profile_name() {
+        println!("Using profile {}", profile);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("validate") {
+        let grants_file = matches.get_one::<String>("grants").unwrap();
+
+        let mut grants = load_grants(grants_file, matches);
+
+        // actions are only checked, compiling them needs DAO accounts
+        prepare_grants(grants_file, &mut grants, matches, &client, &config);
+
+        let actions_count = grants.actions.len()
+            + grants
+                .options
+                .iter()
+                .map(|option| option.actions.len())
+                .sum::<usize>();
+
+        println!(
+            "All {} actions are valid, {} of them are grants",
+            actions_count,
+            grants.grants.len()
+        );
+    }
 
     if let Some(matches) = matches.subcommand_matches("grant") {
         let grants_file = matches.get_one::<String>("grants").unwrap();
 
-        let grants_data = fs::read_to_string(grants_file).unwrap();
+        let mut grants = load_grants(grants_file, matches);
+
+        let decimals = prepare_grants(grants_file, &mut grants, matches, &client, &config);
+
+        if !matches.get_flag("skip-registrar-check") && !grants.grants.is_empty() {
+            check_registrar(&client, &config);
+        }
+
+        if matches.get_flag("group-by-wallet") {
+            for grant in grants.grants.iter_mut() {
+                if grant.group.is_none() {
+                    grant.group = Some(grant.wallet.clone());
+                }
+            }
+        }
+
+        let instructions = grant_instructions(&config, &grants.grants);
+
+        let mut proposal_actions = actions::proposal_actions(&config, &grants, instructions)
+            .unwrap_or_else(|errors| {
+                for error in errors.iter() {
+                    eprintln!("{}: {}", grants_file, error);
+                }
+                process::exit(1);
+            });
 
-        let grants: ProposalData<Grant> = serde_json::from_str(&grants_data).unwrap();
+        if proposal_actions.options.is_empty() {
+            print_actions(&proposal_actions.actions, decimals);
+        } else {
+            println!("{:?} proposal", proposal_actions.vote_type);
 
-        let instructions = grant_instructions(&grants.grants);
+            for option in proposal_actions.options.iter() {
+                println!();
+                println!("Option \"{}\"", option.label);
+                print_actions(&option.actions, decimals);
+            }
+        }
+
+        if matches.get_flag("generate-description") {
+            let decimals = decimals.unwrap_or_else(|| {
+                amount::fetch_mint_decimals(&client, &config.pubkey("MINT")).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    eprintln!("Use --decimals to work offline");
+                    process::exit(1);
+                })
+            });
+
+            let hash = instructions_hash(&proposal_actions.options());
+
+            let document = description::markdown(
+                &proposal_actions,
+                &proposal_actions.description,
+                decimals,
+                &hash,
+            );
+
+            fs::write("../proposal_description.md", &document).unwrap();
+
+            println!();
+            println!("Description saved to proposal_description.md");
+            println!("SHA-256 of the instructions: {}", hex(&hash));
+
+            proposal_actions.description = match matches.get_one::<String>("description-link") {
+                Some(link) => description::link_description(link, &hash),
+                None => document,
+            };
+        }
+
+        // description is sent in the proposal creation transaction, so it has to fit into it
+        let creation_size = description::creation_transaction_size(
+            &proposal_actions,
+            &proposal_actions.description,
+        );
+
+        if creation_size > PACKET_DATA_SIZE {
+            eprintln!(
+                "Proposal creation transaction is {} bytes with the description, limit is {}",
+                creation_size, PACKET_DATA_SIZE
+            );
+
+            if matches.get_one::<String>("description-link").is_none() {
+                eprintln!("Publish the description and pass its link with --description-link");
+            } else {
+                eprintln!("Shorten the proposal name or option labels");
+            }
+
+            process::exit(1);
+        }
+
+        let j = serde_json::to_string(&proposal_actions).unwrap();
+
+        fs::write("../instructions.json", j).unwrap();
+    }
+
+    if let Some(matches) = matches.subcommand_matches("preview") {
+        let grants_file = matches.get_one::<String>("grants").unwrap();
+
+        let mut grants = load_grants(grants_file, matches);
+
+        let decimals = prepare_grants(grants_file, &mut grants, matches, &client, &config);
+
+        let events = vesting::grants_schedule(&grants.grants, validate::now_ts());
+
+        vesting::print_schedule(&grants.grants, &events, decimals);
+
+        vesting::write_schedule_csv(&events, decimals, "../vesting_schedule.csv").unwrap();
+        vesting::write_outflow_csv(&events, decimals, "../treasury_outflow.csv").unwrap();
+
+        println!();
+        println!("Schedule saved to vesting_schedule.csv and treasury_outflow.csv");
+        if grants.grants.iter().any(|grant| grant.start.is_none()) {
+            println!(
+                "Grants without start are counted from now, real start is the moment of execution"
+            );
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("clawback") {
+        let wallets: Vec<Pubkey> = matches
+            .get_many::<String>("WALLET")
+            .unwrap()
+            .map(|wallet| {
+                Pubkey::from_str(wallet).unwrap_or_else(|e| {
+                    eprintln!("Invalid wallet {}: {}", wallet, e);
+                    process::exit(1);
+                })
+            })
+            .collect();
+
+        let instructions = clawback_instructions(&client, &config, &wallets);
+
+        if instructions.is_empty() {
+            eprintln!("Nothing to claw back");
+            process::exit(1);
+        }
+
+        for clawback in instructions.iter() {
+            println!(
+                "{} deposit {}: {:?}, {} locked",
+                clawback.wallet, clawback.deposit_entry_index, clawback.grant_type, clawback.amount
+            );
+        }
 
         let proposal_data = ProposalData {
-            name: grants.name,
-            description: grants.description,
+            name: matches
+                .get_one::<String>("name")
+                .cloned()
+                .unwrap_or_else(|| format!("Clawback grants of {} wallet(s)", wallets.len())),
+            description: matches
+                .get_one::<String>("description")
+                .cloned()
+                .unwrap_or_default(),
             grants: instructions,
+            actions: Vec::new(),
+            options: Vec::new(),
+            vote_type: ProposalVoteType::SingleChoice,
         };
 
         let j = serde_json::to_string(&proposal_data).unwrap();
 
-        fs::write("../instructions.json", j).unwrap();
+        fs::write("../clawback_instructions.json", j).unwrap();
+    }
+
+    if let Some(matches) = matches.subcommand_matches("inspect-voter") {
+        let wallet = matches.get_one::<String>("WALLET").unwrap();
+
+        let wallet = Pubkey::from_str(wallet).unwrap_or_else(|e| {
+            eprintln!("Invalid wallet {}: {}", wallet, e);
+            process::exit(1);
+        });
+
+        let summary = inspect_voter(&client, &config, &wallet);
+
+        if matches.get_flag("json") {
+            println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+        } else {
+            voter::print_voter_summary(&summary);
+        }
     }
 
     if let Some(matches) = matches.subcommand_matches("withdraw") {
-        let deposit = matches.get_one::<u8>("deposit").unwrap();
-        let amount = matches.get_one::<u64>("amount").unwrap();
+        let signer = keypair_or_ledger_of(wallet_path.expect("--wallet is required to withdraw"));
 
-        let withdraw_instruction = withdraw_instruction(signer.pubkey(), *deposit, *amount);
+        let instructions = if matches.get_flag("all") {
+            let withdrawals = withdraw_all_instructions(&client, &config, signer.pubkey());
+
+            if withdrawals.is_empty() {
+                eprintln!("Nothing is unlocked yet");
+                process::exit(1);
+            }
+
+            withdrawals
+        } else {
+            let deposit = matches.get_one::<u8>("deposit").unwrap();
+            let amount = matches.get_one::<u64>("amount").unwrap();
+
+            let registrar_data = voter::load_registrar(
+                &client,
+                &config.pubkey("VOTER_STAKE_PROGRAM"),
+                &config.pubkey("REGISTRAR"),
+            )
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to load registrar: {}", e);
+                process::exit(1);
+            });
+
+            vec![withdraw_instruction(
+                &config,
+                &registrar_data,
+                signer.pubkey(),
+                *deposit,
+                *amount,
+            )]
+        };
+
+        let withdraw_instruction = WithdrawInstruction {
+            wallet: signer.pubkey().to_string(),
+            instructions,
+        };
 
         let j = serde_json::to_string(&withdraw_instruction).unwrap();
 
@@ -212,44 +563,271 @@ fn main() {
     }
 }
 
-pub fn grant_instructions(grants: &[Grant]) -> Vec<GrantInstruction> {
-    let voter_stake_program = Pubkey::from_str(&env::var("VOTER_STAKE_PROGRAM").unwrap()).unwrap();
+fn print_actions(actions: &[ActionInstruction], decimals: Option<u8>) {
+    for (index, action) in actions.iter().enumerate() {
+        let grant = match action {
+            ActionInstruction::Grant(grant) => grant,
+            action => {
+                let group = action
+                    .group()
+                    .map(|group| format!(", group: {}", group))
+                    .unwrap_or_default();
 
-    let mint = Pubkey::from_str(&env::var("MINT").unwrap()).unwrap();
+                println!("{}. {}{}", index + 1, action.describe(), group);
+                continue;
+            }
+        };
 
-    let registrar = Pubkey::from_str(&env::var("REGISTRAR").unwrap()).unwrap();
+        let start = grant
+            .start
+            .map(start_time::format_utc)
+            .unwrap_or_else(|| "at execution".to_string());
 
-    let deposit_token = Pubkey::from_str(&env::var("DEPOSIT_TOKEN").unwrap()).unwrap();
+        let amount = match decimals {
+            Some(decimals) => format!(
+                "{} ({})",
+                amount::amount_to_ui_amount(grant.amount, decimals),
+                grant.amount
+            ),
+            None => grant.amount.to_string(),
+        };
 
-    let deposit_token_auth = Pubkey::from_str(&env::var("DEPOSIT_TOKEN_AUTH").unwrap()).unwrap();
+        let group = grant
+            .group
+            .as_ref()
+            .map(|group| format!(", group: {}", group))
+            .unwrap_or_default();
 
-    let realm_auth = Pubkey::from_str(&env::var("REALM_AUTH").unwrap()).unwrap();
+        println!(
+            "{}. {} {:?} start: {}, periods: {}, amount: {}{}",
+            index + 1,
+            grant.wallet,
+            grant.grant_type,
+            start,
+            grant.periods,
+            amount,
+            group
+        );
+    }
+}
+
+fn grants_file_args(command: Command<'static>) -> Command<'static> {
+    command
+        .arg(
+            arg!(-g --grants "lists of grants, .json or .csv")
+                .required(true)
+                .action(ArgAction::Set),
+        )
+        .arg(arg!(--name [NAME] "proposal name, overrides the one from grants file"))
+        .arg(arg!(--description [DESCRIPTION] "proposal description, overrides the one from grants file"))
+        .arg(
+            arg!(--decimals [DECIMALS] "mint decimals for ui_amount, fetched from the mint if not set")
+                .value_parser(clap::value_parser!(u8)),
+        )
+}
+
+fn load_grants(grants_file: &str, matches: &ArgMatches) -> ProposalData<Grant> {
+    let grants_data = fs::read_to_string(grants_file).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", grants_file, e);
+        process::exit(1);
+    });
+
+    let mut grants: ProposalData<Grant> = if grants_file.ends_with(".csv") {
+        grants_csv::parse_grants_csv(&grants_data).unwrap_or_else(|errors| {
+            for error in errors.iter() {
+                eprintln!("{}: {}", grants_file, error);
+            }
+            process::exit(1);
+        })
+    } else {
+        serde_json::from_str(&grants_data).unwrap_or_else(|e| {
+            eprintln!("Failed to parse {}: {}", grants_file, e);
+            process::exit(1);
+        })
+    };
+
+    if let Some(name) = matches.get_one::<String>("name") {
+        grants.name = name.clone();
+    }
+
+    if let Some(description) = matches.get_one::<String>("description") {
+        grants.description = description.clone();
+    }
+
+    if grants.name.is_empty() {
+        eprintln!("Proposal name is missing, set it in grants file or with --name");
+        process::exit(1);
+    }
+
+    if let Err(e) = lift_options(&mut grants) {
+        eprintln!("{}: {}", grants_file, e);
+        process::exit(1);
+    }
+
+    grants
+}
+
+/// Moves grants of all the options into `grants`, so they are resolved and validated together,
+/// options keep their actions in insertion order
+fn lift_options(data: &mut ProposalData<Grant>) -> Result<(), String> {
+    if data.options.is_empty() {
+        if data.vote_type == ProposalVoteType::MultiChoice {
+            return Err("multi choice proposal needs at least 2 options".to_string());
+        }
+
+        let file_grants = std::mem::take(&mut data.grants);
+        let file_actions = std::mem::take(&mut data.actions);
+        data.actions = actions::lift_grants(&mut data.grants, file_grants, file_actions, None);
+
+        return Ok(());
+    }
+
+    if !data.grants.is_empty() || !data.actions.is_empty() {
+        return Err("when options are set, grants and actions go into the options".to_string());
+    }
+
+    if data.vote_type == ProposalVoteType::MultiChoice && data.options.len() < 2 {
+        return Err("multi choice proposal needs at least 2 options".to_string());
+    }
+
+    if data.options.len() > u8::MAX as usize {
+        return Err(format!("too many options, {} max", u8::MAX));
+    }
+
+    for (index, option) in data.options.iter_mut().enumerate() {
+        if option.label.is_empty() {
+            return Err(format!("label of option {} is empty", index + 1));
+        }
+
+        let option_grants = std::mem::take(&mut option.grants);
+        let option_actions = std::mem::take(&mut option.actions);
+        option.actions = actions::lift_grants(
+            &mut data.grants,
+            option_grants,
+            option_actions,
+            Some(index as u8),
+        );
+    }
+
+    Ok(())
+}
 
-    let payer = Pubkey::from_str(&env::var("PAYER").unwrap()).unwrap();
+/// Resolves `ui_amount`s and validates grants together with other actions,
+/// exits reporting all the problems if there are any
+/// Returns mint decimals if they were needed to resolve amounts or given with `--decimals`
+fn prepare_grants(
+    grants_file: &str,
+    data: &mut ProposalData<Grant>,
+    matches: &ArgMatches,
+    client: &RpcClient,
+    config: &config::Config,
+) -> Option<u8> {
+    let now = validate::now_ts();
+
+    let grants = &mut data.grants;
+
+    let mut issues = start_time::resolve_starts(grants, now);
+
+    let mut decimals = matches.get_one::<u8>("decimals").copied();
+
+    if grants.iter().any(|grant| grant.ui_amount.is_some()) {
+        let mint_decimals = *decimals.get_or_insert_with(|| {
+            let mint = config.pubkey("MINT");
+
+            amount::fetch_mint_decimals(client, &mint).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                eprintln!("Use --decimals to work offline");
+                process::exit(1);
+            })
+        });
+
+        issues.extend(amount::resolve_ui_amounts(grants, mint_decimals));
+    }
+
+    issues.extend(validate::validate_grants(grants, now));
+
+    let problems: Vec<String> = issues
+        .iter()
+        .map(ToString::to_string)
+        .chain(actions::validate_actions(data))
+        .collect();
+
+    if !problems.is_empty() {
+        for problem in problems.iter() {
+            eprintln!("{}: {}", grants_file, problem);
+        }
+
+        eprintln!("Found {} problem(s) in {}", problems.len(), grants_file);
+        process::exit(1);
+    }
+
+    decimals
+}
+
+/// Makes sure grants of `MINT` can be executed: it's a voting mint of the registrar
+/// and `REALM_AUTH` is allowed to grant. Voting power settings of the mint are printed.
+fn check_registrar(client: &RpcClient, config: &config::Config) {
+    let voter_stake_program = config.pubkey("VOTER_STAKE_PROGRAM");
+
+    let registrar = config.pubkey("REGISTRAR");
+
+    let mint = config.pubkey("MINT");
+
+    let realm_auth = config.pubkey("REALM_AUTH");
+
+    let registrar_data = voter::load_registrar(client, &voter_stake_program, &registrar)
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load registrar: {}", e);
+            process::exit(1);
+        });
+
+    let voting_mint = voter::voting_mint_config(&registrar_data, &mint).unwrap_or_else(|e| {
+        eprintln!("Registrar check failed: {}", e);
+        process::exit(1);
+    });
+
+    voter::print_voting_mint_config(voting_mint);
+    println!();
+
+    if realm_auth != registrar_data.realm_authority && realm_auth != voting_mint.grant_authority {
+        eprintln!(
+            "Registrar check failed: REALM_AUTH {} is neither realm authority {} nor grant authority of the mint",
+            realm_auth, registrar_data.realm_authority
+        );
+        process::exit(1);
+    }
+}
+
+pub fn grant_instructions(config: &config::Config, grants: &[Grant]) -> Vec<GrantInstruction> {
+    let voter_stake_program = config.pubkey("VOTER_STAKE_PROGRAM");
+
+    let mint = config.pubkey("MINT");
+
+    let registrar = config.pubkey("REGISTRAR");
+
+    let deposit_token = config.pubkey("DEPOSIT_TOKEN");
+
+    let deposit_token_auth = config.pubkey("DEPOSIT_TOKEN_AUTH");
+
+    let realm_auth = config.pubkey("REALM_AUTH");
+
+    let payer = config.pubkey("PAYER");
 
     let mut instructions = Vec::new();
 
     for grant in grants.iter() {
+        let amount = grant
+            .amount
+            .expect("amount is resolved before generating instructions");
+
         // wallet
         let voter_authority = Pubkey::from_str(&grant.wallet).unwrap();
 
-        let (voter, voter_bump) = Pubkey::find_program_address(
-            &[
-                registrar.as_ref(),
-                "voter".as_bytes(),
-                voter_authority.as_ref(),
-            ],
-            &voter_stake_program,
-        );
+        let (voter, voter_bump) =
+            voter::voter_address(&voter_stake_program, &registrar, &voter_authority);
 
-        let (voter_weight_record, voter_weight_record_bump) = Pubkey::find_program_address(
-            &[
-                registrar.as_ref(),
-                "voter-weight-record".as_bytes(),
-                voter_authority.as_ref(),
-            ],
-            &voter_stake_program,
-        );
+        let (voter_weight_record, voter_weight_record_bump) =
+            voter::voter_weight_record_address(&voter_stake_program, &registrar, &voter_authority);
 
         let vault = spl_associated_token_account::get_associated_token_address(&voter, &mint);
 
@@ -274,11 +852,11 @@ pub fn grant_instructions(grants: &[Grant]) -> Vec<GrantInstruction> {
         let data = voter_stake_registry::instruction::Grant {
             voter_bump,
             voter_weight_record_bump,
-            kind: (&grant.grant_type).into(),
-            start_ts: grant.start,
+            kind: lockup_kind_of(&grant.grant_type),
+            start_ts: grant.start_ts(),
             periods: grant.periods,
             allow_clawback: grant.allow_clawback,
-            amount: grant.amount,
+            amount,
         }
         .data();
 
@@ -293,10 +871,11 @@ pub fn grant_instructions(grants: &[Grant]) -> Vec<GrantInstruction> {
         instructions.push(GrantInstruction {
             wallet: grant.wallet.clone(),
             grant_type: grant.grant_type.clone(),
-            start: grant.start,
+            start: grant.start_ts(),
             periods: grant.periods,
             allow_clawback: grant.allow_clawback,
-            amount: grant.amount,
+            amount,
+            group: grant.group.clone(),
             instruction: instruction_bytes,
         });
     }
@@ -304,37 +883,216 @@ pub fn grant_instructions(grants: &[Grant]) -> Vec<GrantInstruction> {
     instructions
 }
 
+/// Clawback of every deposit entry that allows it and still has locked tokens.
+/// Deposits of one wallet are grouped, so they are clawed back in one proposal transaction.
+pub fn clawback_instructions(
+    client: &RpcClient,
+    config: &config::Config,
+    wallets: &[Pubkey],
+) -> Vec<ClawbackInstruction> {
+    let voter_stake_program = config.pubkey("VOTER_STAKE_PROGRAM");
+
+    let mint = config.pubkey("MINT");
+
+    let registrar = config.pubkey("REGISTRAR");
+
+    let deposit_token = config.pubkey("DEPOSIT_TOKEN");
+
+    let realm_auth = config.pubkey("REALM_AUTH");
+
+    let registrar_data = voter::load_registrar(client, &voter_stake_program, &registrar)
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load registrar: {}", e);
+            process::exit(1);
+        });
+
+    let now = voter::registrar_now(client, &registrar_data).unwrap_or_else(|e| {
+        eprintln!("Failed to get current time: {}", e);
+        process::exit(1);
+    });
+
+    let mut instructions = Vec::new();
+
+    for wallet in wallets.iter() {
+        let (voter, _voter_bump) = voter::voter_address(&voter_stake_program, &registrar, wallet);
+
+        let voter_data =
+            voter::load_voter(client, &voter_stake_program, &voter).unwrap_or_else(|e| {
+                eprintln!("Failed to load voter of {}: {}", wallet, e);
+                process::exit(1);
+            });
+
+        let token_owner_record = voter::token_owner_record_address(&registrar_data, wallet);
+
+        let vault = spl_associated_token_account::get_associated_token_address(&voter, &mint);
+
+        let mut clawbacks = 0;
+
+        for (index, deposit) in voter_data.deposits.iter().enumerate() {
+            let amount = deposit.amount_locked(now);
+
+            if !deposit.is_used || !deposit.allow_clawback || amount == 0 {
+                continue;
+            }
+
+            let accounts = voter_stake_registry::accounts::Clawback {
+                registrar,
+                realm_authority: realm_auth,
+                voter,
+                token_owner_record,
+                vault,
+                destination: deposit_token,
+                token_program: spl_token::id(),
+            }
+            .to_account_metas(None);
+
+            let data = voter_stake_registry::instruction::Clawback {
+                deposit_entry_index: index as u8,
+            }
+            .data();
+
+            let instruction = Instruction {
+                program_id: voter_stake_program,
+                data,
+                accounts,
+            };
+
+            instructions.push(ClawbackInstruction {
+                wallet: wallet.to_string(),
+                grant_type: grant_type_of(deposit.lockup.kind),
+                start: Some(deposit.lockup.start_ts as u64),
+                periods: deposit.lockup.periods_total().unwrap_or_default() as u32,
+                allow_clawback: deposit.allow_clawback,
+                amount,
+                group: Some(wallet.to_string()),
+                deposit_entry_index: index as u8,
+                instruction: bincode::serialize(&instruction).unwrap(),
+            });
+
+            clawbacks += 1;
+        }
+
+        if clawbacks == 0 {
+            println!("{} has no locked deposits that allow clawback", wallet);
+        }
+    }
+
+    instructions
+}
+
+pub fn inspect_voter(
+    client: &RpcClient,
+    config: &config::Config,
+    wallet: &Pubkey,
+) -> voter::VoterSummary {
+    let voter_stake_program = config.pubkey("VOTER_STAKE_PROGRAM");
+
+    let registrar = config.pubkey("REGISTRAR");
+
+    let registrar_data = voter::load_registrar(client, &voter_stake_program, &registrar)
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load registrar: {}", e);
+            process::exit(1);
+        });
+
+    let now = voter::registrar_now(client, &registrar_data).unwrap_or_else(|e| {
+        eprintln!("Failed to get current time: {}", e);
+        process::exit(1);
+    });
+
+    let (voter, _voter_bump) = voter::voter_address(&voter_stake_program, &registrar, wallet);
+
+    let (voter_weight_record, _voter_weight_record_bump) =
+        voter::voter_weight_record_address(&voter_stake_program, &registrar, wallet);
+
+    let voter_data = voter::load_voter(client, &voter_stake_program, &voter).unwrap_or_else(|e| {
+        eprintln!("Failed to load voter of {}: {}", wallet, e);
+        process::exit(1);
+    });
+
+    voter::voter_summary(wallet, &voter, &voter_weight_record, &voter_data, now)
+}
+
+/// Withdraw of everything unlocked from every deposit entry of the wallet
+pub fn withdraw_all_instructions(
+    client: &RpcClient,
+    config: &config::Config,
+    wallet: Pubkey,
+) -> Vec<Vec<u8>> {
+    let voter_stake_program = config.pubkey("VOTER_STAKE_PROGRAM");
+
+    let registrar = config.pubkey("REGISTRAR");
+
+    let registrar_data = voter::load_registrar(client, &voter_stake_program, &registrar)
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load registrar: {}", e);
+            process::exit(1);
+        });
+
+    // unlocked amount only grows with time, so it's still withdrawable when transaction is sent
+    let now = voter::registrar_now(client, &registrar_data).unwrap_or_else(|e| {
+        eprintln!("Failed to get current time: {}", e);
+        process::exit(1);
+    });
+
+    let (voter, _voter_bump) = voter::voter_address(&voter_stake_program, &registrar, &wallet);
+
+    let voter_data = voter::load_voter(client, &voter_stake_program, &voter).unwrap_or_else(|e| {
+        eprintln!("Failed to load voter of {}: {}", wallet, e);
+        process::exit(1);
+    });
+
+    let mut instructions = Vec::new();
+
+    for (index, deposit) in voter_data.deposits.iter().enumerate() {
+        if !deposit.is_used {
+            continue;
+        }
+
+        let amount = deposit.amount_unlocked(now);
+
+        if amount == 0 {
+            continue;
+        }
+
+        println!("deposit {}: withdrawing {}", index, amount);
+
+        instructions.push(withdraw_instruction(
+            config,
+            &registrar_data,
+            wallet,
+            index as u8,
+            amount,
+        ));
+    }
+
+    instructions
+}
+
 pub fn withdraw_instruction(
+    config: &config::Config,
+    registrar_data: &Registrar,
     wallet: Pubkey,
     deposit_entry_index: u8,
     amount: u64,
-) -> WithdrawInstruction {
-    let voter_stake_program = Pubkey::from_str(&env::var("VOTER_STAKE_PROGRAM").unwrap()).unwrap();
+) -> Vec<u8> {
+    let voter_stake_program = config.pubkey("VOTER_STAKE_PROGRAM");
 
-    let registrar = Pubkey::from_str(&env::var("REGISTRAR").unwrap()).unwrap();
+    let registrar = config.pubkey("REGISTRAR");
 
-    let mint = Pubkey::from_str(&env::var("MINT").unwrap()).unwrap();
+    let mint = config.pubkey("MINT");
 
-    let (voter, _voter_bump) = Pubkey::find_program_address(
-        &[registrar.as_ref(), "voter".as_bytes(), wallet.as_ref()],
-        &voter_stake_program,
-    );
+    let (voter, _voter_bump) = voter::voter_address(&voter_stake_program, &registrar, &wallet);
 
-    let (voter_weight_record, _voter_weight_record_bump) = Pubkey::find_program_address(
-        &[
-            registrar.as_ref(),
-            "voter-weight-record".as_bytes(),
-            wallet.as_ref(),
-        ],
-        &voter_stake_program,
-    );
+    let (voter_weight_record, _voter_weight_record_bump) =
+        voter::voter_weight_record_address(&voter_stake_program, &registrar, &wallet);
 
     let vault = spl_associated_token_account::get_associated_token_address(&voter, &mint);
 
     let destination_token_acc =
         spl_associated_token_account::get_associated_token_address(&wallet, &mint);
 
-    let token_owner_record = Pubkey::from_str(&env::var("REALM_AUTH").unwrap()).unwrap();
+    let token_owner_record = voter::token_owner_record_address(registrar_data, &wallet);
 
     let accounts = voter_stake_registry::accounts::Withdraw {
         registrar,
@@ -360,12 +1118,7 @@ pub fn withdraw_instruction(
         accounts,
     };
 
-    let instruction_bytes = bincode::serialize(&instruction).unwrap();
-
-    WithdrawInstruction {
-        wallet: wallet.to_string(),
-        instruction: instruction_bytes,
-    }
+    bincode::serialize(&instruction).unwrap()
 }
 
 fn keypair_or_ledger_of(path: &Path) -> Box<dyn Signer> {
@@ -402,3 +1155,195 @@ fn keypair_or_ledger_of(path: &Path) -> Box<dyn Signer> {
         Box::new(read_keypair_file(path.to_str().unwrap()).unwrap())
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant(wallet: &str) -> Grant {
+        Grant {
+            wallet: wallet.to_string(),
+            grant_type: GrantType::Monthly,
+            start: None,
+            periods: 12,
+            allow_clawback: true,
+            amount: Some(1_000_000),
+            ui_amount: None,
+            group: None,
+            option_index: 0,
+            source: validate::GrantSource::default(),
+        }
+    }
+
+    fn sol_transfer() -> actions::Action {
+        actions::Action::SolTransfer {
+            destination: Pubkey::new_unique().to_string(),
+            lamports: 1,
+            source: None,
+            group: None,
+        }
+    }
+
+    fn option(label: &str, grants: Vec<Grant>, actions: Vec<actions::Action>) -> ProposalOption {
+        ProposalOption {
+            label: label.to_string(),
+            grants,
+            actions,
+        }
+    }
+
+    fn proposal(
+        grants: Vec<Grant>,
+        actions: Vec<actions::Action>,
+        options: Vec<ProposalOption>,
+        vote_type: ProposalVoteType,
+    ) -> ProposalData<Grant> {
+        ProposalData {
+            name: "Proposal".to_string(),
+            description: String::new(),
+            grants,
+            actions,
+            options,
+            vote_type,
+        }
+    }
+
+    fn grant_indexes(actions: &[actions::Action]) -> Vec<Option<usize>> {
+        actions
+            .iter()
+            .map(|action| match action {
+                actions::Action::GrantIndex(index) => Some(*index),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn sources(grants: &[Grant]) -> Vec<String> {
+        grants
+            .iter()
+            .map(|grant| grant.source.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn lifts_grants_without_options() {
+        let mut data = proposal(
+            vec![grant("a"), grant("b")],
+            vec![sol_transfer(), actions::Action::Grant(grant("c"))],
+            Vec::new(),
+            ProposalVoteType::SingleChoice,
+        );
+
+        lift_options(&mut data).unwrap();
+
+        let wallets: Vec<_> = data.grants.iter().map(|grant| &grant.wallet).collect();
+        assert_eq!(wallets, ["a", "b", "c"]);
+        assert!(data.grants.iter().all(|grant| grant.option_index == 0));
+        assert_eq!(
+            grant_indexes(&data.actions),
+            [Some(0), Some(1), None, Some(2)]
+        );
+        assert_eq!(sources(&data.grants), ["grant 1", "grant 2", "action 2"]);
+    }
+
+    #[test]
+    fn lifts_grants_of_every_option() {
+        let mut data = proposal(
+            Vec::new(),
+            Vec::new(),
+            vec![
+                option("First", vec![grant("a")], vec![sol_transfer()]),
+                option(
+                    "Second",
+                    vec![grant("a")],
+                    vec![actions::Action::Grant(grant("b"))],
+                ),
+            ],
+            ProposalVoteType::MultiChoice,
+        );
+
+        lift_options(&mut data).unwrap();
+
+        let grants: Vec<_> = data
+            .grants
+            .iter()
+            .map(|grant| (grant.wallet.as_str(), grant.option_index))
+            .collect();
+        assert_eq!(grants, [("a", 0), ("a", 1), ("b", 1)]);
+        assert!(data.options.iter().all(|option| option.grants.is_empty()));
+        assert_eq!(grant_indexes(&data.options[0].actions), [Some(0), None]);
+        assert_eq!(grant_indexes(&data.options[1].actions), [Some(1), Some(2)]);
+        assert_eq!(
+            sources(&data.grants),
+            ["option 1 grant 1", "option 2 grant 1", "option 2 action 1"]
+        );
+    }
+
+    #[test]
+    fn rejects_multi_choice_with_one_option() {
+        let mut without_options = proposal(
+            vec![grant("a")],
+            Vec::new(),
+            Vec::new(),
+            ProposalVoteType::MultiChoice,
+        );
+        assert!(lift_options(&mut without_options).is_err());
+
+        let mut one_option = proposal(
+            Vec::new(),
+            Vec::new(),
+            vec![option("Only", vec![grant("a")], Vec::new())],
+            ProposalVoteType::MultiChoice,
+        );
+        assert!(lift_options(&mut one_option).is_err());
+
+        one_option.vote_type = ProposalVoteType::SingleChoice;
+        assert!(lift_options(&mut one_option).is_ok());
+    }
+
+    #[test]
+    fn rejects_top_level_grants_with_options() {
+        let mut data = proposal(
+            vec![grant("a")],
+            Vec::new(),
+            vec![option("First", Vec::new(), Vec::new())],
+            ProposalVoteType::SingleChoice,
+        );
+        assert!(lift_options(&mut data).is_err());
+
+        let mut data = proposal(
+            Vec::new(),
+            vec![sol_transfer()],
+            vec![option("First", Vec::new(), Vec::new())],
+            ProposalVoteType::SingleChoice,
+        );
+        assert!(lift_options(&mut data).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_options() {
+        let mut data = proposal(
+            Vec::new(),
+            Vec::new(),
+            vec![
+                option("First", Vec::new(), Vec::new()),
+                option("", Vec::new(), Vec::new()),
+            ],
+            ProposalVoteType::SingleChoice,
+        );
+        assert_eq!(
+            lift_options(&mut data),
+            Err("label of option 2 is empty".to_string())
+        );
+
+        let mut data = proposal(
+            Vec::new(),
+            Vec::new(),
+            (0..256)
+                .map(|index| option(&index.to_string(), Vec::new(), Vec::new()))
+                .collect(),
+            ProposalVoteType::MultiChoice,
+        );
+        assert!(lift_options(&mut data).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use chrono::{DateTime, NaiveDate, SecondsFormat, TimeZone, Utc};
+
+use crate::{validate::GrantIssue, Grant, GrantStart};
+
+/// Parses grant start, it can be:
+/// - Unix timestamp in seconds, as a number or a string `1767225600`
+/// - RFC 3339 date and time `2026-01-01T00:00:00Z`
+/// - plain date `2026-01-01`, midnight UTC
+/// - offset from now `+30d`, units are `s`, `m`, `h`, `d` and `w`
+pub fn parse_start(start: &GrantStart, now: u64) -> Result<u64, String> {
+    let start = match start {
+        GrantStart::Timestamp(timestamp) => return Ok(*timestamp),
+        GrantStart::Date(start) => start.trim(),
+    };
+
+    // checked before the timestamp, `u64` parsing takes `+30` as 30
+    if let Some(offset) = start.strip_prefix('+') {
+        return parse_offset(offset)
+            .and_then(|offset| now.checked_add(offset))
+            .ok_or_else(|| {
+                format!(
+                    "start `+{}` is not a valid offset, expected something like `+30d`",
+                    offset
+                )
+            });
+    }
+
+    if let Ok(timestamp) = start.parse::<u64>() {
+        return Ok(timestamp);
+    }
+
+    let timestamp = if let Ok(date_time) = DateTime::parse_from_rfc3339(start) {
+        date_time.timestamp()
+    } else if let Ok(date) = NaiveDate::parse_from_str(start, "%Y-%m-%d") {
+        date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp()
+    } else {
+        return Err(format!(
+            "start `{}` should be a timestamp, RFC 3339 date, YYYY-MM-DD date or offset like `+30d`",
+            start
+        ));
+    };
+
+    u64::try_from(timestamp).map_err(|_| format!("start `{}` is before 1970", start))
+}
+
+fn parse_offset(offset: &str) -> Option<u64> {
+    let unit = offset.chars().last()?;
+    let value = offset[..offset.len() - unit.len_utf8()]
+        .parse::<u64>()
+        .ok()?;
+
+    let unit_secs = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+
+    value.checked_mul(unit_secs)
+}
+
+pub fn format_utc(timestamp: u64) -> String {
+    i64::try_from(timestamp)
+        .ok()
+        .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single())
+        .map(|date_time| date_time.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// Replaces every grant start with the resolved timestamp
+pub fn resolve_starts(grants: &mut [Grant], now: u64) -> Vec<GrantIssue> {
+    let mut issues = Vec::new();
+
+    for grant in grants.iter_mut() {
+        if let Some(start) = &grant.start {
+            match parse_start(start, now) {
+                Ok(timestamp) => grant.start = Some(GrantStart::Timestamp(timestamp)),
+                Err(message) => issues.push(GrantIssue::new(grant, Some("start"), message)),
+            }
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn parse(start: &str) -> Result<u64, String> {
+        parse_start(&GrantStart::Date(start.to_string()), NOW)
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(
+            parse_start(&GrantStart::Timestamp(1_767_225_600), NOW),
+            Ok(1_767_225_600)
+        );
+        assert_eq!(parse("1767225600"), Ok(1_767_225_600));
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(parse("2026-01-01"), Ok(1_767_225_600));
+        assert_eq!(parse(" 2026-01-01T00:00:00Z "), Ok(1_767_225_600));
+        assert_eq!(parse("2026-01-01T02:00:00+02:00"), Ok(1_767_225_600));
+    }
+
+    #[test]
+    fn parses_offsets() {
+        assert_eq!(parse("+30s"), Ok(NOW + 30));
+        assert_eq!(parse("+5m"), Ok(NOW + 5 * 60));
+        assert_eq!(parse("+2h"), Ok(NOW + 2 * 60 * 60));
+        assert_eq!(parse("+30d"), Ok(NOW + 30 * 24 * 60 * 60));
+        assert_eq!(parse("+1w"), Ok(NOW + 7 * 24 * 60 * 60));
+    }
+
+    #[test]
+    fn rejects_malformed_start() {
+        for start in [
+            "",
+            "+d",
+            "+30",
+            "+30y",
+            "+-1d",
+            "01/01/2026",
+            "1969-12-31",
+            "tomorrow",
+        ] {
+            assert!(parse(start).is_err(), "`{}` is accepted", start);
+        }
+
+        assert!(parse(&format!("+{}w", u64::MAX / 2)).is_err());
+    }
+
+    #[test]
+    fn formats_utc() {
+        assert_eq!(format_utc(1_767_225_600), "2026-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn resolves_starts() {
+        let mut grants: Vec<Grant> = serde_json::from_value(json!([
+            { "wallet": "a", "grant_type": "None", "start": "+1d", "periods": 0,
+              "allow_clawback": false, "amount": 1 },
+            { "wallet": "b", "grant_type": "None", "start": null, "periods": 0,
+              "allow_clawback": false, "amount": 1 },
+            { "wallet": "c", "grant_type": "None", "start": "soon", "periods": 0,
+              "allow_clawback": false, "amount": 1 },
+        ]))
+        .unwrap();
+
+        let issues = resolve_starts(&mut grants, NOW);
+
+        assert_eq!(grants[0].start_ts(), Some(NOW + 24 * 60 * 60));
+        assert_eq!(grants[1].start_ts(), None);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].message.contains("`soon`"));
+    }
+}
@@ -0,0 +1,353 @@
+use solana_sdk::pubkey::Pubkey;
+
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use proposal_actions::GrantType;
+
+use crate::Grant;
+
+/// Grants starting further than 10 years from now are most likely a typo
+pub const MAX_START_OFFSET: u64 = 10 * 365 * 24 * 60 * 60;
+
+/// Anything above this is too big for a seconds timestamp, probably milliseconds were used
+pub const MAX_SECONDS_TIMESTAMP: u64 = 100_000_000_000;
+
+/// Where the grant is written in the grants file, its issues are reported against it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GrantSource {
+    /// 1-based `index` in `grants`, or in `actions` for a `grant` action,
+    /// of the 1-based `option` or of the file itself when it's `None`
+    Entry {
+        option: Option<usize>,
+        action: bool,
+        index: usize,
+    },
+    /// line of CSV file, `columns` are names from its header
+    CsvLine { line: usize, columns: Vec<String> },
+}
+
+impl Default for GrantSource {
+    fn default() -> Self {
+        GrantSource::Entry {
+            option: None,
+            action: false,
+            index: 0,
+        }
+    }
+}
+
+impl fmt::Display for GrantSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrantSource::Entry {
+                option,
+                action,
+                index,
+            } => {
+                if let Some(option) = option {
+                    write!(f, "option {} ", option)?;
+                }
+
+                let list = if *action { "action" } else { "grant" };
+
+                write!(f, "{} {}", list, index)
+            }
+            GrantSource::CsvLine { line, .. } => write!(f, "line {}", line),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GrantIssue {
+    pub source: GrantSource,
+    /// field of the grant the problem is in, `None` if it's about the whole grant
+    pub field: Option<&'static str>,
+    pub message: String,
+}
+
+impl GrantIssue {
+    pub fn new(grant: &Grant, field: Option<&'static str>, message: String) -> GrantIssue {
+        GrantIssue {
+            source: grant.source.clone(),
+            field,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for GrantIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the same format as CSV parsing errors
+        if let GrantSource::CsvLine { line, columns } = &self.source {
+            let column = self
+                .field
+                .and_then(|field| columns.iter().position(|column| column == field));
+
+            if let Some(column) = column {
+                return write!(
+                    f,
+                    "line {}, column {} `{}`: {}",
+                    line,
+                    column + 1,
+                    columns[column],
+                    self.message
+                );
+            }
+        }
+
+        write!(f, "{}: {}", self.source, self.message)
+    }
+}
+
+pub fn now_ts() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Checks every grant and returns all the problems found, empty vec means grants are valid
+pub fn validate_grants(grants: &[Grant], now: u64) -> Vec<GrantIssue> {
+    let mut issues = Vec::new();
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for (index, grant) in grants.iter().enumerate() {
+        let mut report = |field: Option<&'static str>, message: String| {
+            issues.push(GrantIssue::new(grant, field, message))
+        };
+
+        if let Err(e) = Pubkey::from_str(&grant.wallet) {
+            report(
+                Some("wallet"),
+                format!("wallet `{}` is not a valid address: {}", grant.wallet, e),
+            );
+        }
+
+        // zero amount may come from `ui_amount`
+        let amount_field = if grant.ui_amount.is_some() {
+            "ui_amount"
+        } else {
+            "amount"
+        };
+
+        match (grant.amount, &grant.ui_amount) {
+            (Some(0), _) => report(Some(amount_field), "amount is zero".to_string()),
+            (None, None) => report(Some("amount"), "amount is missing".to_string()),
+            _ => {}
+        }
+
+        match grant.grant_type {
+            GrantType::None => {
+                if grant.periods != 0 {
+                    report(
+                        Some("periods"),
+                        format!(
+                            "periods is {} but grant type None doesn't lock tokens, it should be 0",
+                            grant.periods
+                        ),
+                    );
+                }
+            }
+            GrantType::Daily | GrantType::Monthly | GrantType::Cliff | GrantType::Constant => {
+                if grant.periods == 0 {
+                    report(
+                        Some("periods"),
+                        format!(
+                            "periods is 0 for {:?} grant, it should be at least 1",
+                            grant.grant_type
+                        ),
+                    );
+                }
+            }
+        }
+
+        if let Some(start) = grant.start_ts() {
+            if start > MAX_SECONDS_TIMESTAMP {
+                report(
+                    Some("start"),
+                    format!(
+                        "start {} looks like milliseconds, it should be a timestamp in seconds",
+                        start
+                    ),
+                );
+            } else if start > now + MAX_START_OFFSET {
+                report(
+                    Some("start"),
+                    format!("start {} is more than 10 years from now", start),
+                );
+            } else if start < now {
+                report(Some("start"), format!("start {} is in the past", start));
+            }
+        }
+
+        // options are alternatives, the same grant in different options is fine
+        let key = format!(
+            "{}:{}:{:?}:{:?}:{}:{}:{:?}",
+            grant.option_index,
+            grant.wallet,
+            grant.grant_type,
+            grant.start_ts(),
+            grant.periods,
+            grant.allow_clawback,
+            grant.amount
+        );
+
+        if let Some(first) = seen.get(&key) {
+            report(None, format!("duplicate of {}", grants[*first].source));
+        } else {
+            seen.insert(key, index);
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::{json, Value};
+
+    const NOW: u64 = 1_700_000_000;
+
+    /// Valid grant as it's written in grants file, with `fields` replaced
+    fn grant(fields: Value) -> Grant {
+        let mut grant = json!({
+            "wallet": Pubkey::new_unique().to_string(),
+            "grant_type": "Monthly",
+            "start": NOW + 24 * 60 * 60,
+            "periods": 12,
+            "allow_clawback": true,
+            "amount": 1_000_000,
+        });
+
+        for (key, value) in fields.as_object().unwrap() {
+            grant[key] = value.clone();
+        }
+
+        serde_json::from_value(grant).unwrap()
+    }
+
+    fn messages(grants: &[Grant]) -> Vec<String> {
+        let mut grants = grants.to_vec();
+
+        for (index, grant) in grants.iter_mut().enumerate() {
+            grant.source = GrantSource::Entry {
+                option: None,
+                action: false,
+                index: index + 1,
+            };
+        }
+
+        validate_grants(&grants, NOW)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn accepts_valid_grants() {
+        assert!(messages(&[grant(json!({})), grant(json!({}))]).is_empty());
+    }
+
+    #[test]
+    fn reports_duplicates() {
+        let wallet = json!({ "wallet": Pubkey::new_unique().to_string() });
+
+        let grants = [
+            grant(wallet.clone()),
+            grant(json!({})),
+            grant(wallet.clone()),
+            grant(wallet),
+        ];
+
+        assert_eq!(
+            messages(&grants),
+            [
+                "grant 3: duplicate of grant 1",
+                "grant 4: duplicate of grant 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn allows_same_grant_in_different_options() {
+        let wallet = json!({ "wallet": Pubkey::new_unique().to_string() });
+
+        let mut grants = [grant(wallet.clone()), grant(wallet)];
+        grants[1].option_index = 1;
+
+        assert!(messages(&grants).is_empty());
+    }
+
+    #[test]
+    fn grants_differing_in_any_field_are_not_duplicates() {
+        let wallet = Pubkey::new_unique().to_string();
+
+        let grants = [
+            grant(json!({ "wallet": wallet })),
+            grant(json!({ "wallet": wallet, "amount": 2_000_000 })),
+            grant(json!({ "wallet": wallet, "periods": 24 })),
+            grant(json!({ "wallet": wallet, "grant_type": "Daily" })),
+            grant(json!({ "wallet": wallet, "allow_clawback": false })),
+            grant(json!({ "wallet": wallet, "start": NOW + 2 * 24 * 60 * 60 })),
+        ];
+
+        assert!(messages(&grants).is_empty());
+    }
+
+    #[test]
+    fn reports_every_problem_of_a_grant() {
+        let mut bad = grant(json!({
+            "wallet": "not an address",
+            "grant_type": "None",
+            "amount": 0,
+            "start": NOW - 1,
+        }));
+
+        bad.source = GrantSource::Entry {
+            option: Some(2),
+            action: true,
+            index: 3,
+        };
+
+        let issues = validate_grants(&[grant(json!({})), bad], NOW);
+
+        assert_eq!(issues.len(), 4, "{:?}", issues);
+        assert!(issues
+            .iter()
+            .all(|issue| issue.to_string().starts_with("option 2 action 3: ")));
+    }
+
+    #[test]
+    fn checks_periods_of_locked_grants() {
+        let issues = messages(&[grant(json!({ "grant_type": "Cliff", "periods": 0 }))]);
+
+        assert_eq!(
+            issues,
+            ["grant 1: periods is 0 for Cliff grant, it should be at least 1"]
+        );
+    }
+
+    #[test]
+    fn checks_start() {
+        let starts = [
+            (NOW * 1000, "looks like milliseconds"),
+            (NOW + MAX_START_OFFSET + 1, "more than 10 years from now"),
+            (NOW - 1, "in the past"),
+        ];
+
+        for (start, message) in starts {
+            let issues = messages(&[grant(json!({ "start": start }))]);
+
+            assert_eq!(issues.len(), 1);
+            assert!(issues[0].contains(message), "{}", issues[0]);
+        }
+    }
+}
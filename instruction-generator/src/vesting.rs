@@ -0,0 +1,342 @@
+use proposal_actions::GrantType;
+
+use std::collections::BTreeMap;
+
+use crate::{amount, lockup_kind_of, start_time, Grant};
+
+#[derive(Debug, Clone)]
+pub struct UnlockEvent {
+    /// 1-based position of the grant in the grants list
+    pub row: usize,
+    pub wallet: String,
+    pub grant_type: GrantType,
+    pub timestamp: u64,
+    pub amount: u64,
+}
+
+/// Moments and amounts the grant tokens become withdrawable, following voter-stake-registry lockup rules:
+/// - `None` is unlocked right away
+/// - `Daily` and `Monthly` vest linearly, `amount * periods_passed / periods` is unlocked at the end of every period
+/// - `Cliff` unlocks everything at the end of the last period
+/// - `Constant` never unlocks by itself, lockup stays the same until its kind is changed
+pub fn unlock_schedule(
+    grant_type: &GrantType,
+    start: u64,
+    periods: u32,
+    amount: u64,
+) -> Vec<(u64, u64)> {
+    let period_secs = lockup_kind_of(grant_type).period_secs();
+    let periods = periods as u64;
+
+    match grant_type {
+        GrantType::None => vec![(start, amount)],
+        GrantType::Daily | GrantType::Monthly => {
+            let vested = |period: u64| (amount as u128 * period as u128 / periods as u128) as u64;
+
+            (1..=periods)
+                .map(|period| {
+                    (
+                        start + period * period_secs,
+                        vested(period) - vested(period - 1),
+                    )
+                })
+                .filter(|(_, amount)| *amount > 0)
+                .collect()
+        }
+        GrantType::Cliff => vec![(start + periods * period_secs, amount)],
+        GrantType::Constant => vec![],
+    }
+}
+
+/// Unlock events of all the grants sorted by time, grants without start are counted from `now`
+pub fn grants_schedule(grants: &[Grant], now: u64) -> Vec<UnlockEvent> {
+    let mut events = Vec::new();
+
+    for (index, grant) in grants.iter().enumerate() {
+        let start = grant.start_ts().unwrap_or(now);
+        let amount = grant.amount.unwrap_or_default();
+
+        for (timestamp, amount) in unlock_schedule(&grant.grant_type, start, grant.periods, amount)
+        {
+            events.push(UnlockEvent {
+                row: index + 1,
+                wallet: grant.wallet.clone(),
+                grant_type: grant.grant_type.clone(),
+                timestamp,
+                amount,
+            });
+        }
+    }
+
+    events.sort_by_key(|event| event.timestamp);
+
+    events
+}
+
+/// Total amount unlocked at every moment across all the grants
+pub fn outflow_timeline(events: &[UnlockEvent]) -> BTreeMap<u64, u64> {
+    let mut timeline: BTreeMap<u64, u64> = BTreeMap::new();
+
+    for event in events.iter() {
+        let unlocked = timeline.entry(event.timestamp).or_insert(0);
+        *unlocked = unlocked.saturating_add(event.amount);
+    }
+
+    timeline
+}
+
+fn format_amount(amount: u64, decimals: Option<u8>) -> String {
+    match decimals {
+        Some(decimals) => amount::amount_to_ui_amount(amount, decimals),
+        None => amount.to_string(),
+    }
+}
+
+pub fn print_schedule(grants: &[Grant], events: &[UnlockEvent], decimals: Option<u8>) {
+    let mut wallets: Vec<&String> = Vec::new();
+
+    for grant in grants.iter() {
+        if !wallets.contains(&&grant.wallet) {
+            wallets.push(&grant.wallet);
+        }
+    }
+
+    for wallet in wallets {
+        println!("{}", wallet);
+        println!(
+            "  {:<22} {:>4} {:<9} {:>22} {:>22}",
+            "unlock (UTC)", "row", "type", "unlocked", "total unlocked"
+        );
+
+        let mut total: u64 = 0;
+
+        for event in events.iter().filter(|event| &event.wallet == wallet) {
+            total = total.saturating_add(event.amount);
+
+            println!(
+                "  {:<22} {:>4} {:<9} {:>22} {:>22}",
+                start_time::format_utc(event.timestamp),
+                event.row,
+                format!("{:?}", event.grant_type),
+                format_amount(event.amount, decimals),
+                format_amount(total, decimals)
+            );
+        }
+
+        for (index, grant) in grants.iter().enumerate() {
+            if &grant.wallet == wallet && matches!(grant.grant_type, GrantType::Constant) {
+                println!(
+                    "  row {} Constant: {} stays locked for {} day(s) until lockup kind is changed",
+                    index + 1,
+                    format_amount(grant.amount.unwrap_or_default(), decimals),
+                    grant.periods
+                );
+            }
+        }
+
+        println!();
+    }
+
+    println!("Treasury outflow");
+    println!(
+        "  {:<22} {:>22} {:>22}",
+        "unlock (UTC)", "unlocked", "total unlocked"
+    );
+
+    let mut total: u64 = 0;
+
+    for (timestamp, amount) in outflow_timeline(events) {
+        total = total.saturating_add(amount);
+
+        println!(
+            "  {:<22} {:>22} {:>22}",
+            start_time::format_utc(timestamp),
+            format_amount(amount, decimals),
+            format_amount(total, decimals)
+        );
+    }
+}
+
+pub fn write_schedule_csv(
+    events: &[UnlockEvent],
+    decimals: Option<u8>,
+    path: &str,
+) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+
+    writer.write_record([
+        "wallet",
+        "row",
+        "grant_type",
+        "timestamp",
+        "utc",
+        "amount",
+        "ui_amount",
+    ])?;
+
+    for event in events.iter() {
+        writer.write_record([
+            event.wallet.clone(),
+            event.row.to_string(),
+            format!("{:?}", event.grant_type),
+            event.timestamp.to_string(),
+            start_time::format_utc(event.timestamp),
+            event.amount.to_string(),
+            decimals
+                .map(|decimals| amount::amount_to_ui_amount(event.amount, decimals))
+                .unwrap_or_default(),
+        ])?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+pub fn write_outflow_csv(
+    events: &[UnlockEvent],
+    decimals: Option<u8>,
+    path: &str,
+) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+
+    writer.write_record([
+        "timestamp",
+        "utc",
+        "amount",
+        "ui_amount",
+        "total",
+        "ui_total",
+    ])?;
+
+    let mut total: u64 = 0;
+
+    for (timestamp, amount) in outflow_timeline(events) {
+        total = total.saturating_add(amount);
+
+        let ui_amount = |amount: u64| {
+            decimals
+                .map(|decimals| amount::amount_to_ui_amount(amount, decimals))
+                .unwrap_or_default()
+        };
+
+        writer.write_record([
+            timestamp.to_string(),
+            start_time::format_utc(timestamp),
+            amount.to_string(),
+            ui_amount(amount),
+            total.to_string(),
+            ui_amount(total),
+        ])?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    const DAY: u64 = 24 * 60 * 60;
+    const START: u64 = 1_700_000_000;
+
+    #[test]
+    fn none_unlocks_at_start() {
+        assert_eq!(
+            unlock_schedule(&GrantType::None, START, 0, 1_000),
+            [(START, 1_000)]
+        );
+    }
+
+    #[test]
+    fn daily_vests_every_period() {
+        assert_eq!(
+            unlock_schedule(&GrantType::Daily, START, 3, 1_000),
+            [
+                (START + DAY, 333),
+                (START + 2 * DAY, 333),
+                (START + 3 * DAY, 334)
+            ]
+        );
+    }
+
+    #[test]
+    fn vested_parts_add_up_to_amount() {
+        for (grant_type, periods, amount) in [
+            (GrantType::Daily, 7, 1_000_003),
+            (GrantType::Monthly, 12, 1_000_000_000),
+            (GrantType::Monthly, 48, u64::MAX),
+        ] {
+            let schedule = unlock_schedule(&grant_type, START, periods, amount);
+
+            assert_eq!(schedule.len(), periods as usize);
+            assert_eq!(
+                schedule
+                    .iter()
+                    .map(|(_, amount)| *amount as u128)
+                    .sum::<u128>(),
+                amount as u128
+            );
+        }
+    }
+
+    #[test]
+    fn skips_empty_periods() {
+        assert_eq!(
+            unlock_schedule(&GrantType::Daily, START, 4, 2),
+            [(START + 2 * DAY, 1), (START + 4 * DAY, 1)]
+        );
+    }
+
+    #[test]
+    fn cliff_unlocks_at_the_end() {
+        assert_eq!(
+            unlock_schedule(&GrantType::Cliff, START, 30, 1_000),
+            [(START + 30 * DAY, 1_000)]
+        );
+    }
+
+    #[test]
+    fn constant_never_unlocks() {
+        assert!(unlock_schedule(&GrantType::Constant, START, 30, 1_000).is_empty());
+    }
+
+    #[test]
+    fn merges_grants_into_timeline() {
+        let grants: Vec<Grant> = serde_json::from_value(json!([
+            { "wallet": "a", "grant_type": "Cliff", "start": START, "periods": 2,
+              "allow_clawback": false, "amount": 100 },
+            { "wallet": "b", "grant_type": "Daily", "start": null, "periods": 2,
+              "allow_clawback": false, "amount": 10 },
+        ]))
+        .unwrap();
+
+        let events = grants_schedule(&grants, START);
+
+        let rows: Vec<_> = events.iter().map(|event| event.row).collect();
+        assert_eq!(rows, [2, 1, 2]);
+
+        let timeline: Vec<_> = outflow_timeline(&events).into_iter().collect();
+        assert_eq!(timeline, [(START + DAY, 5), (START + 2 * DAY, 105)]);
+    }
+
+    #[test]
+    fn timeline_saturates() {
+        let event = UnlockEvent {
+            row: 1,
+            wallet: "a".to_string(),
+            grant_type: GrantType::None,
+            timestamp: START,
+            amount: u64::MAX,
+        };
+
+        let timeline: Vec<_> = outflow_timeline(&[event.clone(), event])
+            .into_iter()
+            .collect();
+        assert_eq!(timeline, [(START, u64::MAX)]);
+    }
+}
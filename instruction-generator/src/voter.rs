@@ -0,0 +1,270 @@
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, sysvar::clock::Clock};
+use voter_stake_registry::state::{Registrar, Voter, VotingMintConfig};
+
+use std::mem::size_of;
+
+use proposal_actions::GrantType;
+
+use crate::{grant_type_of, start_time};
+
+/// Vote weight factors of voter-stake-registry are fixed point numbers with this base
+static SCALED_FACTOR_BASE: u64 = 1_000_000_000;
+
+#[derive(Serialize, Debug)]
+pub struct DepositSummary {
+    pub index: u8,
+    pub grant_type: GrantType,
+    pub start: i64,
+    pub end: i64,
+    pub periods: u64,
+    pub amount_deposited: u64,
+    pub amount_initially_locked: u64,
+    pub amount_vested: u64,
+    pub amount_withdrawable: u64,
+    pub allow_clawback: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct VoterSummary {
+    pub wallet: String,
+    pub voter: String,
+    pub voter_weight_record: String,
+    /// registrar time the amounts are counted at
+    pub timestamp: i64,
+    pub deposits: Vec<DepositSummary>,
+}
+
+/// voter - Account PDA seeds: [registrar, 'voter', voter_authority]
+pub fn voter_address(
+    voter_stake_program: &Pubkey,
+    registrar: &Pubkey,
+    voter_authority: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            registrar.as_ref(),
+            "voter".as_bytes(),
+            voter_authority.as_ref(),
+        ],
+        voter_stake_program,
+    )
+}
+
+/// voter_weight_record - Account PDA seeds: [registrar, 'voter-weight-record', voter_authority]
+pub fn voter_weight_record_address(
+    voter_stake_program: &Pubkey,
+    registrar: &Pubkey,
+    voter_authority: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            registrar.as_ref(),
+            "voter-weight-record".as_bytes(),
+            voter_authority.as_ref(),
+        ],
+        voter_stake_program,
+    )
+}
+
+/// token_owner_record - Account PDA seeds: ['governance', realm, token_mint, token_owner]
+pub fn token_owner_record_address(registrar: &Registrar, token_owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            "governance".as_ref(),
+            registrar.realm.as_ref(),
+            registrar.realm_governing_token_mint.as_ref(),
+            token_owner.as_ref(),
+        ],
+        &registrar.governance_program_id,
+    )
+    .0
+}
+
+/// Loads zero copy account of voter-stake-registry, data goes after 8 bytes of anchor discriminator
+fn load_zero_copy<T: bytemuck::Pod>(
+    client: &RpcClient,
+    voter_stake_program: &Pubkey,
+    address: &Pubkey,
+) -> Result<T, String> {
+    let account = client
+        .get_account(address)
+        .map_err(|e| format!("failed to load account {}: {}", address, e))?;
+
+    if account.owner != *voter_stake_program {
+        return Err(format!(
+            "account {} is owned by {}, not by voter-stake-registry program {}",
+            address, account.owner, voter_stake_program
+        ));
+    }
+
+    let data = account
+        .data
+        .get(8..8 + size_of::<T>())
+        .ok_or_else(|| format!("account {} has unexpected size", address))?;
+
+    Ok(bytemuck::pod_read_unaligned(data))
+}
+
+pub fn load_voter(
+    client: &RpcClient,
+    voter_stake_program: &Pubkey,
+    voter: &Pubkey,
+) -> Result<Voter, String> {
+    load_zero_copy(client, voter_stake_program, voter)
+}
+
+pub fn load_registrar(
+    client: &RpcClient,
+    voter_stake_program: &Pubkey,
+    registrar: &Pubkey,
+) -> Result<Registrar, String> {
+    load_zero_copy(client, voter_stake_program, registrar)
+}
+
+/// Current time as voter-stake-registry sees it: cluster clock shifted by registrar's `time_offset`
+pub fn registrar_now(client: &RpcClient, registrar: &Registrar) -> Result<i64, String> {
+    let account = client
+        .get_account(&solana_sdk::sysvar::clock::id())
+        .map_err(|e| format!("failed to load clock: {}", e))?;
+
+    let clock: Clock =
+        bincode::deserialize(&account.data).map_err(|e| format!("failed to read clock: {}", e))?;
+
+    Ok(clock.unix_timestamp + registrar.time_offset)
+}
+
+/// Active deposit entries of the voter with amounts counted at `now`
+pub fn voter_summary(
+    wallet: &Pubkey,
+    voter: &Pubkey,
+    voter_weight_record: &Pubkey,
+    voter_data: &Voter,
+    now: i64,
+) -> VoterSummary {
+    let deposits = voter_data
+        .deposits
+        .iter()
+        .enumerate()
+        .filter(|(_, deposit)| deposit.is_used)
+        .map(|(index, deposit)| DepositSummary {
+            index: index as u8,
+            grant_type: grant_type_of(deposit.lockup.kind),
+            start: deposit.lockup.start_ts,
+            end: deposit.lockup.end_ts,
+            periods: deposit.lockup.periods_total().unwrap_or_default(),
+            amount_deposited: deposit.amount_deposited_native,
+            amount_initially_locked: deposit.amount_initially_locked_native,
+            amount_vested: deposit.vested(now).unwrap_or_default(),
+            amount_withdrawable: deposit.amount_unlocked(now),
+            allow_clawback: deposit.allow_clawback,
+        })
+        .collect();
+
+    VoterSummary {
+        wallet: wallet.to_string(),
+        voter: voter.to_string(),
+        voter_weight_record: voter_weight_record.to_string(),
+        timestamp: now,
+        deposits,
+    }
+}
+
+pub fn print_voter_summary(summary: &VoterSummary) {
+    println!("wallet:              {}", summary.wallet);
+    println!("voter:               {}", summary.voter);
+    println!("voter weight record: {}", summary.voter_weight_record);
+    println!(
+        "registrar time:      {}",
+        start_time::format_utc(summary.timestamp.max(0) as u64)
+    );
+    println!();
+
+    if summary.deposits.is_empty() {
+        println!("No active deposits");
+        return;
+    }
+
+    println!(
+        "{:>5} {:<9} {:<22} {:<22} {:>7} {:>20} {:>20} {:>20} {:>20} {:>8}",
+        "index",
+        "type",
+        "start (UTC)",
+        "end (UTC)",
+        "periods",
+        "deposited",
+        "initially locked",
+        "vested",
+        "withdrawable",
+        "clawback"
+    );
+
+    for deposit in summary.deposits.iter() {
+        println!(
+            "{:>5} {:<9} {:<22} {:<22} {:>7} {:>20} {:>20} {:>20} {:>20} {:>8}",
+            deposit.index,
+            format!("{:?}", deposit.grant_type),
+            start_time::format_utc(deposit.start.max(0) as u64),
+            start_time::format_utc(deposit.end.max(0) as u64),
+            deposit.periods,
+            deposit.amount_deposited,
+            deposit.amount_initially_locked,
+            deposit.amount_vested,
+            deposit.amount_withdrawable,
+            if deposit.allow_clawback { "yes" } else { "no" }
+        );
+    }
+}
+
+/// Voting mint config of `mint`, grants of mints not configured in the registrar fail at execution
+pub fn voting_mint_config<'a>(
+    registrar: &'a Registrar,
+    mint: &Pubkey,
+) -> Result<&'a VotingMintConfig, String> {
+    registrar
+        .voting_mints
+        .iter()
+        .find(|config| config.mint == *mint)
+        .ok_or_else(|| {
+            format!(
+                "mint {} is not a voting mint of the registrar, configured mints: {}",
+                mint,
+                registrar
+                    .voting_mints
+                    .iter()
+                    .filter(|config| config.mint != Pubkey::default())
+                    .map(|config| config.mint.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+fn scaled_factor(factor: u64) -> String {
+    format!("{:.3}", factor as f64 / SCALED_FACTOR_BASE as f64)
+}
+
+pub fn print_voting_mint_config(config: &VotingMintConfig) {
+    println!("Voting mint {}", config.mint);
+    println!(
+        "  exchange rate:                 vote weight = amount * 10^{}",
+        config.digit_shift
+    );
+    println!(
+        "  baseline vote weight:          {}x",
+        scaled_factor(config.baseline_vote_weight_scaled_factor)
+    );
+    println!(
+        "  max extra lockup vote weight:  {}x",
+        scaled_factor(config.max_extra_lockup_vote_weight_scaled_factor)
+    );
+    println!(
+        "  lockup saturation:             {} day(s)",
+        config.lockup_saturation_secs / 86_400
+    );
+    println!(
+        "  grant authority:               {}",
+        config.grant_authority
+    );
+}
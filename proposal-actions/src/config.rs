@@ -0,0 +1,121 @@
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use std::{collections::BTreeMap, env, fs, process, str::FromStr};
+
+/// Keys of DAO accounts, env var names. Profile keys are the same in lower case.
+pub static KEYS: [&str; 10] = [
+    "VOTER_STAKE_PROGRAM",
+    "MINT",
+    "REGISTRAR",
+    "DEPOSIT_TOKEN",
+    "DEPOSIT_TOKEN_AUTH",
+    "REALM_AUTH",
+    "PAYER",
+    "GOVERNANCE_PROGRAM",
+    "GOVERNANCE",
+    "COUNCIL_MINT",
+];
+
+#[derive(Deserialize, Debug, Default)]
+struct ConfigFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// DAO accounts taken from a named profile of TOML config:
+///
+/// ``` toml
+/// [profiles.mango]
+/// voter_stake_program = "address"
+/// mint = "address"
+/// ```
+///
+/// Without profile they are read from env vars(`.env`), keys missing in the profile fall back to env.
+#[derive(Debug)]
+pub struct Config {
+    profile: Option<(String, String, BTreeMap<String, String>)>,
+}
+
+impl Config {
+    pub fn load(path: &str, profile: Option<&str>) -> Result<Config, String> {
+        let profile = match profile {
+            Some(profile) => profile,
+            None => return Ok(Config { profile: None }),
+        };
+
+        let data =
+            fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+
+        let mut config: ConfigFile =
+            toml::from_str(&data).map_err(|e| format!("failed to parse {}: {}", path, e))?;
+
+        let values = config.profiles.remove(profile).ok_or_else(|| {
+            format!(
+                "profile `{}` is not found in {}, available: {}",
+                profile,
+                path,
+                config
+                    .profiles
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+
+        for key in values.keys() {
+            if !KEYS.contains(&key.to_uppercase().as_str()) || *key != key.to_lowercase() {
+                return Err(format!(
+                    "unknown key `{}` in profile `{}` of {}",
+                    key, profile, path
+                ));
+            }
+        }
+
+        Ok(Config {
+            profile: Some((profile.to_string(), path.to_string(), values)),
+        })
+    }
+
+    pub fn profile_name(&self) -> Option<&str> {
+        self.profile.as_ref().map(|(name, _, _)| name.as_str())
+    }
+
+    pub fn try_pubkey(&self, key: &str) -> Result<Pubkey, String> {
+        let profile_key = key.to_lowercase();
+
+        match &self.profile {
+            Some((name, path, values)) if values.contains_key(&profile_key) => {
+                Pubkey::from_str(&values[&profile_key]).map_err(|e| {
+                    format!(
+                        "`{}` in profile `{}` of {} is not a valid address: {}",
+                        profile_key, name, path, e
+                    )
+                })
+            }
+            profile => {
+                let value = env::var(key).map_err(|_| match profile {
+                    Some((name, path, _)) => format!(
+                        "`{}` is missing in profile `{}` of {} and {} is not set",
+                        profile_key, name, path, key
+                    ),
+                    None => format!(
+                        "{} is not set, add it to .env or select a profile with --profile",
+                        key
+                    ),
+                })?;
+
+                Pubkey::from_str(&value)
+                    .map_err(|e| format!("{} is not a valid address: {}", key, e))
+            }
+        }
+    }
+
+    pub fn pubkey(&self, key: &str) -> Pubkey {
+        self.try_pubkey(key).unwrap_or_else(|e| {
+            eprintln!("Config error: {}", e);
+            process::exit(1);
+        })
+    }
+}
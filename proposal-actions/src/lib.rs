@@ -0,0 +1,361 @@
+//! Compiled proposal actions, the format of instructions file instruction-generator writes
+//! and proposal-creator reads, and DAO accounts config both of them load
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    hash::{hash, Hash},
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+
+pub mod config;
+
+/// Label of the single option of a proposal without options
+pub const DEFAULT_OPTION_LABEL: &str = "Approve";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum GrantType {
+    None,
+    Daily,
+    Monthly,
+    Cliff,
+    Constant,
+}
+
+/// With single choice voters pick one option, with multi choice any number of them
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalVoteType {
+    #[default]
+    SingleChoice,
+    MultiChoice,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OptionActions {
+    pub label: String,
+    pub actions: Vec<ActionInstruction>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GrantInstruction {
+    pub wallet: String,
+    pub grant_type: GrantType,
+    pub start: Option<u64>,
+    pub periods: u32,
+    pub allow_clawback: bool,
+    pub amount: u64,
+    #[serde(default)]
+    pub group: Option<String>,
+    pub instruction: Vec<u8>,
+}
+
+/// Compiled action, proposal-creator inserts them in the order of the list
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActionInstruction {
+    Grant(GrantInstruction),
+    SplTransfer(TransferInstruction),
+    SolTransfer(TransferInstruction),
+    MintTo(TransferInstruction),
+    RawInstruction(RawInstruction),
+}
+
+/// Transfer or mint, `source` is the mint for `mint_to`, `amount` is in lamports for `sol_transfer`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransferInstruction {
+    pub source: String,
+    pub destination: String,
+    pub amount: u64,
+    #[serde(default)]
+    pub group: Option<String>,
+    pub instruction: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RawInstruction {
+    pub program_id: String,
+    #[serde(default)]
+    pub group: Option<String>,
+    pub instruction: Vec<u8>,
+}
+
+impl ActionInstruction {
+    /// Short name of the action for logs
+    pub fn label(&self) -> String {
+        match self {
+            ActionInstruction::Grant(grant) => grant.wallet.clone(),
+            ActionInstruction::SplTransfer(transfer) => {
+                format!("spl transfer to {}", transfer.destination)
+            }
+            ActionInstruction::SolTransfer(transfer) => {
+                format!("sol transfer to {}", transfer.destination)
+            }
+            ActionInstruction::MintTo(transfer) => format!("mint to {}", transfer.destination),
+            ActionInstruction::RawInstruction(raw) => format!("instruction of {}", raw.program_id),
+        }
+    }
+
+    /// Full description of the action for previews
+    pub fn describe(&self) -> String {
+        match self {
+            ActionInstruction::Grant(grant) => format!("grant to {}", grant.wallet),
+            ActionInstruction::SplTransfer(transfer) => format!(
+                "spl transfer of {} from {} to {}",
+                transfer.amount, transfer.source, transfer.destination
+            ),
+            ActionInstruction::SolTransfer(transfer) => format!(
+                "sol transfer of {} lamports from {} to {}",
+                transfer.amount, transfer.source, transfer.destination
+            ),
+            ActionInstruction::MintTo(transfer) => format!(
+                "mint of {} {} to {}",
+                transfer.amount, transfer.source, transfer.destination
+            ),
+            ActionInstruction::RawInstruction(raw) => {
+                format!("instruction of program {}", raw.program_id)
+            }
+        }
+    }
+
+    pub fn group(&self) -> Option<&String> {
+        match self {
+            ActionInstruction::Grant(grant) => grant.group.as_ref(),
+            ActionInstruction::SplTransfer(transfer)
+            | ActionInstruction::SolTransfer(transfer)
+            | ActionInstruction::MintTo(transfer) => transfer.group.as_ref(),
+            ActionInstruction::RawInstruction(raw) => raw.group.as_ref(),
+        }
+    }
+
+    /// bincode serialized `Instruction`
+    pub fn instruction(&self) -> &Vec<u8> {
+        match self {
+            ActionInstruction::Grant(grant) => &grant.instruction,
+            ActionInstruction::SplTransfer(transfer)
+            | ActionInstruction::SolTransfer(transfer)
+            | ActionInstruction::MintTo(transfer) => &transfer.instruction,
+            ActionInstruction::RawInstruction(raw) => &raw.instruction,
+        }
+    }
+}
+
+/// Actions with the same group make one proposal transaction, placed where the first of them is.
+/// Actions without group get a transaction each.
+pub fn group_actions(actions: &[ActionInstruction]) -> Vec<Vec<&ActionInstruction>> {
+    let mut groups: Vec<Vec<&ActionInstruction>> = Vec::new();
+
+    for action in actions.iter() {
+        let group = action.group().and_then(|group| {
+            groups
+                .iter_mut()
+                .find(|actions| actions[0].group() == Some(group))
+        });
+
+        match group {
+            Some(group) => group.push(action),
+            None => groups.push(vec![action]),
+        }
+    }
+
+    groups
+}
+
+fn push_len(bytes: &mut Vec<u8>, len: usize) {
+    bytes.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+fn push_bytes(bytes: &mut Vec<u8>, data: &[u8]) {
+    push_len(bytes, data.len());
+    bytes.extend_from_slice(data);
+}
+
+/// Canonical serialization of what gets inserted into the proposal:
+///
+/// ``` text
+/// options count, then for every option:
+///     label, transactions count, then for every transaction:
+///         instructions count, then every instruction
+/// ```
+///
+/// Counts are u32 little endian, label(UTF-8) and instruction(bincode serialized `Instruction`,
+/// as in instructions file) are prefixed with their length as u32 little endian.
+pub fn canonical_instructions(options: &[OptionActions]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+
+    push_len(&mut bytes, options.len());
+
+    for option in options.iter() {
+        push_bytes(&mut bytes, option.label.as_bytes());
+
+        let transactions = group_actions(&option.actions);
+
+        push_len(&mut bytes, transactions.len());
+
+        for transaction in transactions.iter() {
+            push_len(&mut bytes, transaction.len());
+
+            for action in transaction.iter() {
+                push_bytes(&mut bytes, action.instruction());
+            }
+        }
+    }
+
+    bytes
+}
+
+/// SHA-256 of `canonical_instructions`, it covers options, their transactions and instructions.
+/// It doesn't cover the description itself, so it can be put into the description.
+pub fn instructions_hash(options: &[OptionActions]) -> Hash {
+    hash(&canonical_instructions(options))
+}
+
+/// `Hash` displays as base58, sha256 tools print hex
+pub fn hex(hash: &Hash) -> String {
+    hash.to_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Serialized size of the transaction with given instructions, signatures included
+pub fn transaction_size(payer: &Pubkey, instructions: &[Instruction]) -> usize {
+    let tx = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+
+    bincode::serialized_size(&tx).unwrap() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(group: Option<&str>, byte: u8) -> ActionInstruction {
+        ActionInstruction::RawInstruction(RawInstruction {
+            program_id: "program".to_string(),
+            group: group.map(str::to_string),
+            instruction: vec![byte],
+        })
+    }
+
+    fn bytes(groups: &[Vec<&ActionInstruction>]) -> Vec<Vec<u8>> {
+        groups
+            .iter()
+            .map(|group| group.iter().map(|action| action.instruction()[0]).collect())
+            .collect()
+    }
+
+    fn option(label: &str, actions: Vec<ActionInstruction>) -> OptionActions {
+        OptionActions {
+            label: label.to_string(),
+            actions,
+        }
+    }
+
+    #[test]
+    fn groups_actions_where_the_first_one_is() {
+        let actions = [
+            action(Some("a"), 0),
+            action(None, 1),
+            action(Some("b"), 2),
+            action(Some("a"), 3),
+            action(None, 4),
+            action(Some("b"), 5),
+        ];
+
+        assert_eq!(
+            bytes(&group_actions(&actions)),
+            [vec![0, 3], vec![1], vec![2, 5], vec![4]]
+        );
+    }
+
+    #[test]
+    fn actions_without_group_are_not_grouped() {
+        let actions = [action(None, 0), action(None, 1)];
+
+        assert_eq!(bytes(&group_actions(&actions)), [vec![0], vec![1]]);
+        assert!(group_actions(&[]).is_empty());
+    }
+
+    #[test]
+    fn reads_actions_by_type() {
+        let actions: Vec<ActionInstruction> = serde_json::from_str(
+            r#"[
+                { "type": "grant", "wallet": "a", "grant_type": "Monthly", "start": null,
+                  "periods": 12, "allow_clawback": true, "amount": 5, "instruction": [1] },
+                { "type": "sol_transfer", "source": "b", "destination": "c", "amount": 7,
+                  "group": "g", "instruction": [2] }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(actions[0].label(), "a");
+        assert_eq!(actions[0].group(), None);
+        assert_eq!(
+            actions[1].describe(),
+            "sol transfer of 7 lamports from b to c"
+        );
+        assert_eq!(actions[1].group().map(String::as_str), Some("g"));
+        assert_eq!(actions[1].instruction(), &vec![2]);
+    }
+
+    #[test]
+    fn serializes_canonically() {
+        let options = [option(
+            "Approve",
+            vec![action(Some("a"), 7), action(Some("a"), 8)],
+        )];
+
+        assert_eq!(
+            canonical_instructions(&options),
+            [
+                vec![1, 0, 0, 0],
+                vec![7, 0, 0, 0],
+                b"Approve".to_vec(),
+                vec![1, 0, 0, 0],
+                vec![2, 0, 0, 0],
+                vec![1, 0, 0, 0, 7],
+                vec![1, 0, 0, 0, 8],
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn hash_covers_options_and_transactions() {
+        let hash = |options: &[OptionActions]| instructions_hash(options);
+
+        let one_option = hash(&[option("Approve", vec![action(None, 0), action(None, 1)])]);
+
+        let moved = hash(&[
+            option("Approve", vec![action(None, 0)]),
+            option("Other", vec![action(None, 1)]),
+        ]);
+        let relabeled = hash(&[option("Other", vec![action(None, 0), action(None, 1)])]);
+        let grouped = hash(&[option(
+            "Approve",
+            vec![action(Some("a"), 0), action(Some("a"), 1)],
+        )]);
+        let reordered = hash(&[option("Approve", vec![action(None, 1), action(None, 0)])]);
+
+        for other in [moved, relabeled, grouped, reordered] {
+            assert_ne!(one_option, other);
+        }
+
+        // groups names aren't inserted, only the transactions they make
+        let renamed_group = hash(&[option(
+            "Approve",
+            vec![action(Some("b"), 0), action(Some("b"), 1)],
+        )]);
+        assert_eq!(grouped, renamed_group);
+    }
+
+    #[test]
+    fn formats_hash_as_hex() {
+        assert_eq!(
+            hex(&hash(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}
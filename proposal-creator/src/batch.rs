@@ -0,0 +1,169 @@
+use solana_sdk::{instruction::Instruction, packet::PACKET_DATA_SIZE, pubkey::Pubkey};
+
+use std::collections::VecDeque;
+
+pub use proposal_actions::transaction_size;
+
+use crate::simulation::SimulationResult;
+
+pub fn fits_in_transaction(payer: &Pubkey, instructions: &[Instruction]) -> bool {
+    transaction_size(payer, instructions) <= PACKET_DATA_SIZE
+}
+
+/// Takes as many items from the front as their instructions fit into one transaction.
+/// `instruction` builds instruction for the item following the ones already in the batch.
+/// At least one item is taken, even if it doesn't fit alone, so sending it reports the error.
+pub fn take_batch<T>(
+    payer: &Pubkey,
+    items: &mut VecDeque<T>,
+    instruction: impl Fn(&T, &[T]) -> Instruction,
+) -> (Vec<T>, Vec<Instruction>) {
+    let mut batch = Vec::new();
+    let mut instructions = Vec::new();
+
+    while let Some(item) = items.front() {
+        let next_instruction = instruction(item, &batch);
+
+        instructions.push(next_instruction);
+
+        if !batch.is_empty() && !fits_in_transaction(payer, &instructions) {
+            instructions.pop();
+            break;
+        }
+
+        batch.push(items.pop_front().unwrap());
+    }
+
+    (batch, instructions)
+}
+
+/// Halves the batch, giving the rest back to the front of `items`, until its simulation succeeds
+/// or a single item is left. Batch that fits by size may still exceed the compute limit
+/// of one transaction. `instructions` are the ones of `batch`, one per item.
+pub fn shrink_batch<T>(
+    items: &mut VecDeque<T>,
+    batch: &mut Vec<T>,
+    instructions: &mut Vec<Instruction>,
+    simulate: impl Fn(&[Instruction]) -> SimulationResult,
+) -> SimulationResult {
+    loop {
+        let result = simulate(instructions);
+
+        if result.is_ok() || batch.len() <= 1 {
+            return result;
+        }
+
+        let rest = batch.split_off(batch.len() / 2);
+        instructions.truncate(batch.len());
+
+        for item in rest.into_iter().rev() {
+            items.push_front(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instruction(program_id: &Pubkey, size: usize) -> Instruction {
+        Instruction::new_with_bytes(*program_id, &vec![0; size], Vec::new())
+    }
+
+    #[test]
+    fn fills_batches_up_to_the_limit() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+
+        let mut items: VecDeque<usize> = vec![300; 10].into();
+        let mut batches = Vec::new();
+
+        while !items.is_empty() {
+            let (batch, instructions) = take_batch(&payer, &mut items, |size, _| {
+                instruction(&program_id, *size)
+            });
+
+            assert_eq!(batch.len(), instructions.len());
+            assert!(fits_in_transaction(&payer, &instructions));
+
+            if let Some(next) = items.front() {
+                let mut with_next = instructions.clone();
+                with_next.push(instruction(&program_id, *next));
+
+                assert!(!fits_in_transaction(&payer, &with_next));
+            }
+
+            batches.push(batch.len());
+        }
+
+        assert_eq!(batches, [3, 3, 3, 1]);
+    }
+
+    #[test]
+    fn takes_oversized_item_alone() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+
+        let mut items: VecDeque<usize> = vec![10, PACKET_DATA_SIZE, 10].into();
+        let mut take = || {
+            take_batch(&payer, &mut items, |size, _| {
+                instruction(&program_id, *size)
+            })
+        };
+
+        assert_eq!(take().0, [10]);
+
+        let (batch, instructions) = take();
+        assert_eq!(batch, [PACKET_DATA_SIZE]);
+        assert!(!fits_in_transaction(&payer, &instructions));
+
+        assert_eq!(take().0, [10]);
+        assert!(take().0.is_empty());
+    }
+
+    #[test]
+    fn shrinks_batch_until_simulation_succeeds() {
+        let program_id = Pubkey::new_unique();
+
+        let mut items: VecDeque<usize> = vec![6, 7].into();
+        let mut batch = vec![1, 2, 3, 4, 5];
+        let mut instructions: Vec<_> = batch
+            .iter()
+            .map(|size| instruction(&program_id, *size))
+            .collect();
+
+        // e.g. compute limit allows only two executions
+        let result = shrink_batch(&mut items, &mut batch, &mut instructions, |instructions| {
+            SimulationResult {
+                error: (instructions.len() > 2).then(|| "exceeded CUs".to_string()),
+                logs: Vec::new(),
+                units_consumed: None,
+            }
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(batch, [1, 2]);
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(items, [3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn passes_items_already_in_the_batch() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+
+        let mut items: VecDeque<usize> = vec![1, 2, 3].into();
+
+        let (batch, instructions) = take_batch(&payer, &mut items, |_, taken| {
+            instruction(&program_id, taken.iter().sum())
+        });
+
+        assert_eq!(batch, [1, 2, 3]);
+
+        let sizes: Vec<_> = instructions
+            .iter()
+            .map(|instruction| instruction.data.len())
+            .collect();
+        assert_eq!(sizes, [0, 1, 3]);
+    }
+}
@@ -0,0 +1,138 @@
+use borsh::BorshDeserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use spl_governance::state::{
+    governance::GovernanceV2, native_treasury::get_native_treasury_address, realm::RealmV2,
+};
+
+/// Account discovered for a key of `config::KEYS`
+pub struct DiscoveredAccount {
+    pub key: &'static str,
+    pub address: Pubkey,
+    /// Not read from chain but the usual setup, it has to be confirmed before use
+    pub suggested: bool,
+}
+
+/// DAO accounts derived from the governance, in the order of `config::KEYS`:
+/// - realm authority and mints come from the realm of the governance, missing ones are skipped
+/// - community mint is suggested as `MINT`, grants may be made in another voting mint of the registrar
+/// - `REGISTRAR` is voter-stake-registry PDA of the realm and its community mint
+/// - native treasury of the governance is suggested as the grants payer and authority of
+///   `DEPOSIT_TOKEN`, which is suggested to be its associated token account of `MINT`
+pub fn discover(
+    client: &RpcClient,
+    governance_program: &Pubkey,
+    governance_key: &Pubkey,
+    voter_stake_program: &Pubkey,
+) -> Result<Vec<DiscoveredAccount>, String> {
+    let governance_bytes = client
+        .get_account_data(governance_key)
+        .map_err(|e| format!("failed to load governance {}: {}", governance_key, e))?;
+    let governance = GovernanceV2::deserialize(&mut governance_bytes.as_ref())
+        .map_err(|e| format!("{} is not a governance account: {}", governance_key, e))?;
+
+    let realm_bytes = client
+        .get_account_data(&governance.realm)
+        .map_err(|e| format!("failed to load realm {}: {}", governance.realm, e))?;
+    let realm = RealmV2::deserialize(&mut realm_bytes.as_ref())
+        .map_err(|e| format!("{} is not a realm account: {}", governance.realm, e))?;
+
+    if realm.authority.is_none() {
+        warn!(
+            "Realm {} has no authority, REALM_AUTH is skipped",
+            governance.realm
+        );
+    }
+
+    if realm.config.council_mint.is_none() {
+        warn!(
+            "Realm {} has no council mint, COUNCIL_MINT is skipped",
+            governance.realm
+        );
+    }
+
+    let mint = realm.community_mint;
+
+    // registrar - Account PDA seeds: [realm, 'registrar', realm_governing_token_mint]
+    let registrar = Pubkey::find_program_address(
+        &[
+            governance.realm.as_ref(),
+            "registrar".as_bytes(),
+            mint.as_ref(),
+        ],
+        voter_stake_program,
+    )
+    .0;
+
+    if client.get_account(&registrar).is_err() {
+        warn!(
+            "Registrar {} doesn't exist, check VOTER_STAKE_PROGRAM",
+            registrar
+        );
+    }
+
+    let native_treasury = get_native_treasury_address(governance_program, governance_key);
+
+    let deposit_token =
+        spl_associated_token_account::get_associated_token_address(&native_treasury, &mint);
+
+    if client.get_token_account_balance(&deposit_token).is_err() {
+        warn!(
+            "Token account {} of the native treasury doesn't exist yet",
+            deposit_token
+        );
+    }
+
+    let found = |key, address| DiscoveredAccount {
+        key,
+        address,
+        suggested: false,
+    };
+    let suggested = |key, address| DiscoveredAccount {
+        key,
+        address,
+        suggested: true,
+    };
+
+    let accounts = vec![
+        Some(found("VOTER_STAKE_PROGRAM", *voter_stake_program)),
+        Some(suggested("MINT", mint)),
+        Some(found("REGISTRAR", registrar)),
+        Some(suggested("DEPOSIT_TOKEN", deposit_token)),
+        Some(suggested("DEPOSIT_TOKEN_AUTH", native_treasury)),
+        realm
+            .authority
+            .map(|realm_auth| found("REALM_AUTH", realm_auth)),
+        Some(suggested("PAYER", native_treasury)),
+        Some(found("GOVERNANCE_PROGRAM", *governance_program)),
+        Some(found("GOVERNANCE", *governance_key)),
+        realm
+            .config
+            .council_mint
+            .map(|council_mint| found("COUNCIL_MINT", council_mint)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    Ok(accounts)
+}
+
+/// Profile with the accounts in config file format, suggested ones are marked with a comment
+pub fn profile_toml(name: &str, accounts: &[DiscoveredAccount]) -> String {
+    let mut toml = format!("[profiles.{}]\n", name);
+
+    for account in accounts.iter() {
+        if account.suggested {
+            toml.push_str("# suggested, confirm it's the account the DAO uses\n");
+        }
+
+        toml.push_str(&format!(
+            "{} = \"{}\"\n",
+            account.key.to_lowercase(),
+            account.address
+        ));
+    }
+
+    toml
+}
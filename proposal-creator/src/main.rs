@@ -5,6 +5,10 @@ extern crate log;
 use borsh::BorshDeserialize;
 use clap::{arg, command, value_parser, ArgAction, Command};
 use dotenv::dotenv;
+use proposal_actions::{
+    config, group_actions, hex, instructions_hash, ActionInstruction, GrantInstruction,
+    OptionActions, ProposalVoteType, DEFAULT_OPTION_LABEL,
+};
 use serde::{Deserialize, Serialize};
 use solana_client::rpc_client::RpcClient;
 use solana_remote_wallet::locator::Locator;
@@ -12,55 +16,102 @@ use solana_remote_wallet::remote_keypair::generate_remote_keypair;
 use solana_remote_wallet::remote_wallet::maybe_wallet_manager;
 use solana_sdk::derivation_path::DerivationPath;
 use solana_sdk::{
-    self, instruction::Instruction, pubkey::Pubkey, signature::Signer,
-    signer::keypair::read_keypair_file, transaction::Transaction,
+    self,
+    instruction::{AccountMeta, Instruction},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signer,
+    signer::keypair::read_keypair_file,
+    transaction::Transaction,
 };
 use spl_governance::{
     instruction::{
         add_signatory, create_proposal as create_proposal_instruction, execute_transaction,
-        insert_transaction, sign_off_proposal,
+        finalize_vote, insert_transaction, sign_off_proposal,
     },
     state::{
+        enums::{ProposalState, TransactionExecutionStatus},
         governance::GovernanceV2,
-        proposal::{get_proposal_address, VoteType},
+        native_treasury::get_native_treasury_address,
+        proposal::{get_proposal_address, OptionVoteResult, VoteType},
         proposal_transaction::{get_proposal_transaction_address, InstructionData},
     },
 };
 use uriparse::URIReference;
 
 use std::{
-    env, fs,
+    collections::{BTreeSet, VecDeque},
+    fs,
     path::{Path, PathBuf},
+    process,
     str::FromStr,
+    thread,
+    time::Duration,
 };
 
+mod batch;
+mod discover;
+mod proposal;
+mod simulation;
+mod treasury;
+mod verify;
+
 pub static RETRIES: u8 = 5;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum GrantType {
-    None,
-    Daily,
-    Monthly,
-    Cliff,
-    Constant,
-}
+/// How often proposal state is checked while waiting for execution
+pub static WAIT_INTERVAL_SECS: u64 = 30;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProposalData {
     pub name: String,
     pub description: String,
+    /// grants and clawbacks, inserted before `actions`
+    #[serde(default)]
     pub grants: Vec<GrantInstruction>,
+    #[serde(default)]
+    pub actions: Vec<ActionInstruction>,
+    /// options with their own actions, top level ones go to `Approve` option when it's empty
+    #[serde(default)]
+    pub options: Vec<OptionActions>,
+    #[serde(default)]
+    pub vote_type: ProposalVoteType,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct GrantInstruction {
-    pub wallet: String,
-    pub grant_type: GrantType,
-    pub start: Option<u64>,
-    pub periods: u32,
-    pub allow_clawback: bool,
-    pub amount: u64,
-    pub instruction: Vec<u8>,
+impl ProposalData {
+    /// Options with everything to insert under them, in the order of insertion
+    pub fn options(&self) -> Vec<OptionActions> {
+        if !self.options.is_empty() {
+            return self.options.clone();
+        }
+
+        let actions = self
+            .grants
+            .iter()
+            .cloned()
+            .map(ActionInstruction::Grant)
+            .chain(self.actions.iter().cloned())
+            .collect();
+
+        vec![OptionActions {
+            label: DEFAULT_OPTION_LABEL.to_string(),
+            actions,
+        }]
+    }
+
+    /// Multi choice lets voters pick any number of options, all of them may win
+    pub fn vote_type(&self) -> VoteType {
+        match self.vote_type {
+            ProposalVoteType::SingleChoice => VoteType::SingleChoice,
+            ProposalVoteType::MultiChoice => {
+                let options = self.options().len() as u8;
+
+                VoteType::MultiChoice {
+                    max_voter_options: options,
+                    max_winning_options: options,
+                }
+            }
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -73,7 +124,10 @@ pub struct TransactionsToExecute {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProposalTransaction {
     pub address: String,
-    pub instruction: Vec<u8>,
+    #[serde(default)]
+    pub option_index: u8,
+    #[serde(alias = "instruction", deserialize_with = "deserialize_instructions")]
+    pub instructions: Vec<Vec<u8>>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -84,13 +138,32 @@ pub struct ErroneousProposalTransactions {
     pub proposal_owner_record: String,
     pub governance_authority: String,
     pub option_index: u8,
-    pub instruction: Vec<u8>,
+    #[serde(alias = "instruction", deserialize_with = "deserialize_instructions")]
+    pub instructions: Vec<Vec<u8>>,
+}
+
+/// Files written before grants were grouped have a single `instruction` per transaction
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TransactionInstructions {
+    Many(Vec<Vec<u8>>),
+    One(Vec<u8>),
+}
+
+fn deserialize_instructions<'de, D>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match TransactionInstructions::deserialize(deserializer)? {
+        TransactionInstructions::Many(instructions) => instructions,
+        TransactionInstructions::One(instruction) => vec![instruction],
+    })
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WithdrawInstruction {
     pub wallet: String,
-    pub instruction: Vec<u8>,
+    pub instructions: Vec<Vec<u8>>,
 }
 
 fn main() {
@@ -100,7 +173,7 @@ fn main() {
     let matches = command!()
         .arg(
             arg!(
-                -w --wallet <FILE> "Fee payer wallet"
+                -w --wallet [FILE] "Fee payer wallet"
             )
             .value_parser(value_parser!(PathBuf)),
         )
@@ -110,6 +183,8 @@ fn main() {
             )
             .default_value("https://api.mainnet-beta.solana.com/"),
         )
+        .arg(arg!(--config [FILE] "TOML config with DAO profiles").default_value("../config.toml"))
+        .arg(arg!(--profile [NAME] "DAO profile from config, env vars are used if not set"))
         .subcommand(
             Command::new("create-proposal")
                 .about("creates new DAO proposal and attaches bunch of Grant transactions to it")
@@ -117,8 +192,59 @@ fn main() {
                     arg!(-i --instructions "lists of grants to be created")
                         .required(true)
                         .action(ArgAction::Set),
+                )
+                .arg(
+                    arg!(--"dry-run" "simulate all the transactions without sending them")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--force "create proposal even if treasury doesn't have enough tokens")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--"allow-duplicate-name" "create proposal even if proposal with the same name exists")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--proposal [ADDRESS] "insert into this draft proposal instead of creating a new one, it's left in Draft")
+                        .value_parser(value_parser!(Pubkey)),
+                )
+                .arg(
+                    arg!(--draft "leave proposal in Draft for review, sign it off with sign-off command")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--signatory [ADDRESS] "council member signing off the new proposal, our wallet by default")
+                        .value_parser(value_parser!(Pubkey)),
+                ),
+        )
+        .subcommand(
+            Command::new("sign-off")
+                .about("checks transactions inserted into the proposal against instructions file and signs it off")
+                .arg(
+                    arg!(-i --instructions "instructions the proposal was created from")
+                        .required(true)
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    arg!(--proposal [ADDRESS] "proposal to sign off, the one from transaction_to_execute.json by default")
+                        .value_parser(value_parser!(Pubkey)),
+                )
+                .arg(
+                    arg!(--"allow-extra" "sign off even if proposal has transactions that aren't in instructions file")
+                        .action(ArgAction::SetTrue),
                 ),
         )
+        .subcommand(
+            Command::new("hash")
+                .about("prints SHA-256 of the instructions, the one generated descriptions carry")
+                .arg(
+                    arg!(-i --instructions "instructions file")
+                        .required(true)
+                        .action(ArgAction::Set),
+                )
+                .arg(arg!(-o --output [FILE] "file to write the hashed bytes to, to check the hash with sha256sum")),
+        )
         .subcommand(
             Command::new("execute")
                 .about("execute proposal transactions")
@@ -126,6 +252,38 @@ fn main() {
                     arg!(-t --transactions "lists of transactions to be executed")
                         .required(true)
                         .action(ArgAction::Set),
+                )
+                .arg(
+                    arg!(--"dry-run" "simulate all the transactions without sending them")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    arg!(--wait "wait until voting succeeds and hold-up time passes")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("retry")
+                .about("inserts transactions that failed during proposal creation again")
+                .arg(
+                    arg!(-e --erroneous "list of transactions failed to be inserted")
+                        .required(true)
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    arg!(--draft "leave proposal in Draft for review, sign it off with sign-off command")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("discover")
+                .about("derives DAO accounts from GOVERNANCE, GOVERNANCE_PROGRAM and VOTER_STAKE_PROGRAM")
+                .arg(
+                    arg!(--name [NAME] "name of the discovered profile, selected profile name by default"),
+                )
+                .arg(
+                    arg!(-o --output [FILE] "config file to write the profile to")
+                        .default_value("../discovered.toml"),
                 ),
         )
         .subcommand(
@@ -139,14 +297,104 @@ fn main() {
         )
         .get_matches();
 
-    let wallet_path = matches.get_one::<PathBuf>("wallet").unwrap();
-
-    let signer = keypair_or_ledger_of(wallet_path);
+    let wallet_path = matches.get_one::<PathBuf>("wallet");
 
     let node_url = matches.get_one::<String>("node").unwrap();
 
     let client = RpcClient::new(node_url);
 
+    let config = config::Config::load(
+        matches.get_one::<String>("config").unwrap(),
+        matches
+            .get_one::<String>("profile")
+            .map(|profile| profile.as_str()),
+    )
+    .unwrap_or_else(|e| {
+        error!("Config error: {}", e);
+        process::exit(1);
+    });
+
+    if let Some(profile) = config.profile_name() {
+        info!("Using profile {}", profile);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("discover") {
+        let accounts = discover::discover(
+            &client,
+            &config.pubkey("GOVERNANCE_PROGRAM"),
+            &config.pubkey("GOVERNANCE"),
+            &config.pubkey("VOTER_STAKE_PROGRAM"),
+        )
+        .unwrap_or_else(|e| {
+            error!("Discovery failed: {}", e);
+            process::exit(1);
+        });
+
+        let mut conflicts = 0;
+
+        for account in accounts.iter() {
+            let (key, value) = (account.key, account.address);
+
+            match config.try_pubkey(key) {
+                Ok(entered) if entered != value && account.suggested => {
+                    info!("{} is {}, suggested {}", key, entered, value)
+                }
+                Ok(entered) if entered != value => {
+                    warn!("{} is {}, but discovered {}", key, entered, value);
+                    conflicts += 1;
+                }
+                Ok(_) => info!("{} = {}", key, value),
+                Err(_) if account.suggested => {
+                    info!("{} = {} (suggested, confirm it)", key, value)
+                }
+                Err(_) => info!("{} = {} (new)", key, value),
+            }
+        }
+
+        let name = matches
+            .get_one::<String>("name")
+            .map(|name| name.as_str())
+            .or_else(|| config.profile_name())
+            .unwrap_or("discovered");
+
+        let output = matches.get_one::<String>("output").unwrap();
+
+        fs::write(output, discover::profile_toml(name, &accounts)).unwrap();
+
+        info!("Profile {} saved to {}", name, output);
+
+        if conflicts > 0 {
+            warn!(
+                "{} discovered account(s) differ from the configured ones, check them before using the profile",
+                conflicts
+            );
+        }
+
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("hash") {
+        let instructions_file = matches.get_one::<String>("instructions").unwrap();
+
+        let grants_data = fs::read_to_string(instructions_file).unwrap();
+
+        let grants: ProposalData = serde_json::from_str(&grants_data).unwrap();
+
+        let options = grants.options();
+
+        if let Some(output) = matches.get_one::<String>("output") {
+            fs::write(output, proposal_actions::canonical_instructions(&options)).unwrap();
+
+            info!("Hashed bytes saved to {}", output);
+        }
+
+        println!("{}", hex(&instructions_hash(&options)));
+
+        return;
+    }
+
+    let signer = keypair_or_ledger_of(wallet_path.expect("--wallet is required"));
+
     if let Some(matches) = matches.subcommand_matches("create-proposal") {
         let instructions_file = matches.get_one::<String>("instructions").unwrap();
 
@@ -154,7 +402,60 @@ fn main() {
 
         let grants: ProposalData = serde_json::from_str(&grants_data).unwrap();
 
-        create_proposal(&client, &*signer, &grants);
+        let dry_run = matches.get_flag("dry-run");
+        let force = matches.get_flag("force");
+        let allow_duplicate_name = matches.get_flag("allow-duplicate-name");
+        let attach_to = matches.get_one::<Pubkey>("proposal").copied();
+        // proposal drafted by someone else is signed off after its review with `sign-off`
+        let draft = matches.get_flag("draft") || attach_to.is_some();
+        let signatory = matches
+            .get_one::<Pubkey>("signatory")
+            .copied()
+            .unwrap_or_else(|| signer.pubkey());
+
+        create_proposal(
+            &client,
+            &*signer,
+            &config,
+            &grants,
+            attach_to,
+            signatory,
+            dry_run,
+            force,
+            allow_duplicate_name,
+            draft,
+        );
+    }
+
+    if let Some(matches) = matches.subcommand_matches("sign-off") {
+        let instructions_file = matches.get_one::<String>("instructions").unwrap();
+
+        let grants_data = fs::read_to_string(instructions_file).unwrap();
+
+        let grants: ProposalData = serde_json::from_str(&grants_data).unwrap();
+
+        let proposal_address = match matches.get_one::<Pubkey>("proposal") {
+            Some(proposal_address) => *proposal_address,
+            None => {
+                let transactions_data =
+                    fs::read_to_string("../transaction_to_execute.json").unwrap();
+                let transactions: TransactionsToExecute =
+                    serde_json::from_str(&transactions_data).unwrap();
+
+                Pubkey::from_str(&transactions.proposal).unwrap()
+            }
+        };
+
+        let allow_extra = matches.get_flag("allow-extra");
+
+        verify_and_sign_off(
+            &client,
+            &*signer,
+            &config,
+            &grants,
+            &proposal_address,
+            allow_extra,
+        );
     }
 
     if let Some(matches) = matches.subcommand_matches("execute") {
@@ -164,7 +465,23 @@ fn main() {
 
         let transactions: TransactionsToExecute = serde_json::from_str(&transactions_data).unwrap();
 
-        execute_proposal(&client, &*signer, &transactions);
+        let dry_run = matches.get_flag("dry-run");
+        let wait = matches.get_flag("wait");
+
+        execute_proposal(&client, &*signer, &config, &transactions, dry_run, wait);
+    }
+
+    if let Some(matches) = matches.subcommand_matches("retry") {
+        let erroneous_file = matches.get_one::<String>("erroneous").unwrap();
+
+        let erroneous_data = fs::read_to_string(erroneous_file).unwrap();
+
+        let erroneous_transactions: Vec<ErroneousProposalTransactions> =
+            serde_json::from_str(&erroneous_data).unwrap();
+
+        let draft = matches.get_flag("draft");
+
+        retry_insert(&client, &*signer, &erroneous_transactions, draft);
     }
 
     if let Some(matches) = matches.subcommand_matches("execute-withdraw") {
@@ -174,17 +491,21 @@ fn main() {
 
         let instruction: WithdrawInstruction = serde_json::from_str(&instruction_data).unwrap();
 
-        execute_withdraw(&client, &*signer, &instruction);
+        execute_withdraw(&client, &*signer, &config, &instruction);
     }
 }
 
-fn send_tx_with_retry(client: &RpcClient, signer: &dyn Signer, instruction: Instruction) -> bool {
+fn send_tx_with_retry(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    instructions: &[Instruction],
+) -> bool {
     let mut count = RETRIES;
     while count > 0 {
         let blockhash = client.get_latest_blockhash().ok();
         if let Some(blockhash) = blockhash {
             let tx = Transaction::new_signed_with_payer(
-                &[instruction.clone()],
+                instructions,
                 Some(&signer.try_pubkey().unwrap()),
                 &[&*signer],
                 blockhash,
@@ -213,10 +534,22 @@ fn send_tx_with_retry(client: &RpcClient, signer: &dyn Signer, instruction: Inst
     false
 }
 
-fn create_proposal(client: &RpcClient, signer: &dyn Signer, data: &ProposalData) {
-    let governance_program = Pubkey::from_str(&env::var("GOVERNANCE_PROGRAM").unwrap()).unwrap();
-    let governance_key = Pubkey::from_str(&env::var("GOVERNANCE").unwrap()).unwrap();
-    let council_mint = Pubkey::from_str(&env::var("COUNCIL_MINT").unwrap()).unwrap();
+#[allow(clippy::too_many_arguments)]
+fn create_proposal(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    config: &config::Config,
+    data: &ProposalData,
+    attach_to: Option<Pubkey>,
+    signatory: Pubkey,
+    dry_run: bool,
+    force: bool,
+    allow_duplicate_name: bool,
+    draft: bool,
+) {
+    let governance_program = config.pubkey("GOVERNANCE_PROGRAM");
+    let governance_key = config.pubkey("GOVERNANCE");
+    let council_mint = config.pubkey("COUNCIL_MINT");
 
     let governance_bytes = client.get_account_data(&governance_key).unwrap();
     let governance_data = GovernanceV2::deserialize(&mut governance_bytes.as_ref()).unwrap();
@@ -233,195 +566,1143 @@ fn create_proposal(client: &RpcClient, signer: &dyn Signer, data: &ProposalData)
     )
     .0;
 
-    let proposal_instruction = create_proposal_instruction(
-        &governance_program,
-        &governance_key,
-        &proposal_owner_record,
-        &signer.try_pubkey().unwrap(),
-        &signer.try_pubkey().unwrap(),
-        None,
-        &governance_data.realm,
-        data.name.clone(),
-        data.description.clone(),
-        &council_mint,
-        VoteType::SingleChoice,
-        vec!["Approve".to_string()],
-        true,
-        governance_data.proposals_count,
-    );
-
-    let proposal_address = get_proposal_address(
-        &governance_program,
-        &governance_key,
-        &council_mint,
-        &governance_data.proposals_count.to_le_bytes(),
-    );
-
-    let add_signatory_instruction = add_signatory(
-        &governance_program,
-        &proposal_address,
-        &proposal_owner_record,
-        &signer.try_pubkey().unwrap(),
-        &signer.try_pubkey().unwrap(),
-        &signer.try_pubkey().unwrap(),
-    );
-
-    // TODO: call through retry fn
-    let blockhash = client.get_latest_blockhash().unwrap();
+    let options = data.options();
 
-    let tx = Transaction::new_signed_with_payer(
-        &[proposal_instruction, add_signatory_instruction],
-        Some(&signer.try_pubkey().unwrap()),
-        &[&*signer],
-        blockhash,
-    );
-    let signature = client.send_and_confirm_transaction(&tx).unwrap();
+    let option_labels: Vec<String> = options.iter().map(|option| option.label.clone()).collect();
 
-    info!("Proposal was created: {:?}", signature);
+    // deny option is the way to reject the proposal with single choice,
+    // with multi choice voters reject by not picking an option
+    let use_deny_option = data.vote_type == ProposalVoteType::SingleChoice;
 
-    let mut proposal_tx_index: u16 = 0;
+    let existing_proposal = match attach_to {
+        // proposal drafted by someone else, its name and description are kept
+        Some(proposal_address) => {
+            match proposal::load_draft_to_attach(
+                client,
+                &governance_key,
+                &proposal_address,
+                &signer.try_pubkey().unwrap(),
+            ) {
+                Ok(proposal_data) => {
+                    info!(
+                        "Attaching transactions to draft proposal {} \"{}\"",
+                        proposal_address, proposal_data.name
+                    );
+
+                    Some((proposal_address, proposal_data))
+                }
+                Err(e) => {
+                    error!("Can't attach to proposal {}: {}", proposal_address, e);
+                    return;
+                }
+            }
+        }
+        None => match proposal::find_proposal_by_name(
+            client,
+            &governance_program,
+            &governance_key,
+            &council_mint,
+            governance_data.proposals_count,
+            &proposal_owner_record,
+            &data.name,
+        ) {
+            // Draft proposal with the same name means previous run was interrupted, so we continue it
+            Some((proposal_address, proposal_data))
+                if proposal_data.state == ProposalState::Draft =>
+            {
+                Some((proposal_address, proposal_data))
+            }
+            // most likely the same instructions were proposed already
+            Some((proposal_address, proposal_data)) => {
+                if !allow_duplicate_name {
+                    error!(
+                        "Proposal {} with the same name already exists in {:?} state, nothing was sent. Use --allow-duplicate-name to create another one",
+                        proposal_address, proposal_data.state
+                    );
+                    return;
+                }
 
-    let mut error_happen = false;
+                warn!(
+                    "Proposal {} with the same name already exists in {:?} state, creating another one because of --allow-duplicate-name",
+                    proposal_address, proposal_data.state
+                );
 
-    let mut proposal_transactions = Vec::new();
+                None
+            }
+            None => None,
+        },
+    };
 
-    let mut erroneous_transactions = Vec::new();
+    // attached proposal may be owned by another record we control, e.g. the community one
+    let proposal_owner_record = existing_proposal
+        .as_ref()
+        .map(|(_, proposal_data)| proposal_data.token_owner_record)
+        .unwrap_or(proposal_owner_record);
 
-    for grant in data.grants.iter() {
-        let instruction: Instruction = bincode::deserialize(&grant.instruction).unwrap();
-        let instruction_data = InstructionData::from(instruction);
+    // proposal address is derived from its index, which is the proposals count of the governance
+    let proposal_creation = |proposals_count: u32| {
+        let proposal_instruction = create_proposal_instruction(
+            &governance_program,
+            &governance_key,
+            &proposal_owner_record,
+            &signer.try_pubkey().unwrap(),
+            &signer.try_pubkey().unwrap(),
+            None,
+            &governance_data.realm,
+            data.name.clone(),
+            data.description.clone(),
+            &council_mint,
+            data.vote_type(),
+            option_labels.clone(),
+            use_deny_option,
+            proposals_count,
+        );
 
-        let insert_instruction = insert_transaction(
+        let proposal_address = get_proposal_address(
             &governance_program,
             &governance_key,
+            &council_mint,
+            &proposals_count.to_le_bytes(),
+        );
+
+        let add_signatory_instruction = add_signatory(
+            &governance_program,
             &proposal_address,
             &proposal_owner_record,
             &signer.try_pubkey().unwrap(),
             &signer.try_pubkey().unwrap(),
-            0,
-            proposal_tx_index,
-            0,
-            vec![instruction_data],
+            &signatory,
         );
 
-        info!("Adding new transaction to the proposal...");
-        let tx_sent = send_tx_with_retry(client, &*signer, insert_instruction);
+        (
+            proposal_address,
+            vec![proposal_instruction, add_signatory_instruction],
+        )
+    };
 
-        if tx_sent {
-            let transaction_address = get_proposal_transaction_address(
-                &governance_program,
-                &proposal_address,
-                &(0_u8).to_le_bytes(),
-                &proposal_tx_index.to_le_bytes(),
-            );
+    let (mut proposal_address, creation_instructions) = match &existing_proposal {
+        Some((proposal_address, proposal_data)) => {
+            let existing_labels: Vec<&String> = proposal_data
+                .options
+                .iter()
+                .map(|option| &option.label)
+                .collect();
+
+            if existing_labels != option_labels.iter().collect::<Vec<_>>() {
+                error!(
+                    "Draft proposal {} has different options: {:?}",
+                    proposal_address, existing_labels
+                );
+                return;
+            }
 
-            proposal_tx_index += 1;
+            if attach_to.is_none() {
+                info!(
+                    "Draft proposal {} with the same name already exists, continuing with it",
+                    proposal_address
+                );
+            }
 
-            proposal_transactions.push(ProposalTransaction {
-                address: transaction_address.to_string(),
-                instruction: grant.instruction.clone(),
-            });
-        } else {
-            error_happen = true;
+            (*proposal_address, None)
+        }
+        None => {
+            let (proposal_address, creation_instructions) =
+                proposal_creation(governance_data.proposals_count);
 
-            erroneous_transactions.push(ErroneousProposalTransactions {
-                governance_program: governance_program.to_string(),
-                governance_key: governance_key.to_string(),
-                proposal_address: proposal_address.to_string(),
-                proposal_owner_record: proposal_owner_record.to_string(),
-                governance_authority: signer.try_pubkey().unwrap().to_string(),
-                option_index: 0,
-                instruction: grant.instruction.clone(),
-            });
+            (proposal_address, Some(creation_instructions))
         }
-    }
+    };
 
-    let transactions_to_execute = TransactionsToExecute {
-        governance: governance_key.to_string(),
-        proposal: proposal_address.to_string(),
-        transactions: proposal_transactions,
+    let treasury = treasury::Treasury {
+        voter_stake_program: config.pubkey("VOTER_STAKE_PROGRAM"),
+        deposit_token: config.pubkey("DEPOSIT_TOKEN"),
+        mint: config.pubkey("MINT"),
     };
 
-    let j = serde_json::to_string(&transactions_to_execute).unwrap();
+    let enough_tokens = check_treasury(
+        client,
+        &treasury,
+        data,
+        &governance_program,
+        &governance_key,
+        &[council_mint, treasury.mint],
+        governance_data.proposals_count,
+        existing_proposal.as_ref().map(|(address, _)| address),
+    );
 
-    fs::write("../transaction_to_execute.json", j).unwrap();
+    if !enough_tokens {
+        if force {
+            warn!("Treasury doesn't have enough tokens, continuing because of --force");
+        } else {
+            error!("Treasury doesn't have enough tokens, nothing was sent. Use --force to create proposal anyway");
+            return;
+        }
+    }
 
-    info!("Transactions that should be executed successfully saved");
+    // transactions already inserted under each option and next free index of the option
+    let (mut existing_transactions, mut proposal_tx_indexes): (Vec<_>, Vec<u16>) =
+        match &existing_proposal {
+            Some((proposal_address, proposal_data)) => (0..options.len())
+                .map(|option_index| {
+                    (
+                        proposal::load_proposal_transactions(
+                            client,
+                            &governance_program,
+                            proposal_address,
+                            proposal_data,
+                            option_index as u8,
+                        ),
+                        proposal_data.options[option_index].transactions_next_index,
+                    )
+                })
+                .unzip(),
+            None => (vec![Vec::new(); options.len()], vec![0; options.len()]),
+        };
 
-    if error_happen {
-        let j = serde_json::to_string(&erroneous_transactions).unwrap();
+    let mut proposal_transactions = Vec::new();
 
-        fs::write("../erroneous_txs.json", j).unwrap();
+    let mut grants_to_insert = Vec::new();
 
-        warn!("During proposal creation error had happened, thats why proposal WAS NOT signed off");
-        info!("All the erroneous transactions were saved to erroneous_txs.json");
-        info!("You can try to insert it again with `retry` command"); // TODO: maybe redact a little
-    } else {
-        let sign_off_proposal = sign_off_proposal(
-            &governance_program,
-            &governance_data.realm,
-            &governance_key,
-            &proposal_address,
-            &signer.try_pubkey().unwrap(),
-            None,
-        );
+    let mut oversized_transactions = 0;
 
-        let blockhash = client.get_latest_blockhash().unwrap();
+    for (option_index, option) in options.iter().enumerate() {
+        for group in group_actions(&option.actions) {
+            let label = group
+                .iter()
+                .map(|action| action.label())
+                .collect::<Vec<_>>()
+                .join(", ");
 
-        let tx = Transaction::new_signed_with_payer(
-            &[sign_off_proposal],
-            Some(&signer.try_pubkey().unwrap()),
-            &[&*signer],
-            blockhash,
-        );
-        let signature = client.send_and_confirm_transaction(&tx).unwrap();
+            let instructions_bytes: Vec<Vec<u8>> = group
+                .iter()
+                .map(|action| action.instruction().clone())
+                .collect();
 
-        info!(
-            "Proposal was signed off: {:?}\nWe are ready for voting",
-            signature
-        );
-    }
-}
+            // grouped actions are executed in one transaction, so it must fit into the size limit
+            let execution = execute_instruction(
+                &governance_program,
+                &governance_key,
+                &proposal_address,
+                &Pubkey::default(),
+                &instructions_bytes,
+            );
 
-fn execute_proposal(client: &RpcClient, signer: &dyn Signer, data: &TransactionsToExecute) {
-    let governance_program = Pubkey::from_str(&env::var("GOVERNANCE_PROGRAM").unwrap()).unwrap();
-    let governance_key = Pubkey::from_str(&data.governance).unwrap();
-    let proposal_key = Pubkey::from_str(&data.proposal).unwrap();
+            // only governance PDAs are signed for, other signers have to sign the execution themselves
+            let signers = execution_signers(&execution);
+
+            if !signers.is_empty() {
+                warn!(
+                "Transaction for {} requires signature of {}, it has to be executed by that wallet",
+                label,
+                signers
+                    .iter()
+                    .map(|signer| signer.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            }
 
-    let number_of_transactions = data.transactions.len();
+            let execution_size =
+                batch::transaction_size(&signer.try_pubkey().unwrap(), &[execution]);
 
-    let mut error_happen = false;
+            if execution_size > PACKET_DATA_SIZE {
+                error!(
+                "Group of {} actions ({}) doesn't fit into one execution transaction, it's {} bytes, limit is {}, split it into smaller ones",
+                group.len(),
+                label,
+                execution_size,
+                PACKET_DATA_SIZE
+            );
+                oversized_transactions += 1;
+                continue;
+            }
 
-    let mut erroneous_transactions = Vec::new();
+            let instructions: Vec<InstructionData> = instructions_bytes
+                .iter()
+                .map(|bytes| {
+                    let instruction: Instruction = bincode::deserialize(bytes).unwrap();
+                    InstructionData::from(instruction)
+                })
+                .collect();
+
+            // the group is inserted as one proposal transaction, its insert has to fit as well
+            let insert = insert_transaction(
+                &governance_program,
+                &governance_key,
+                &proposal_address,
+                &proposal_owner_record,
+                &signer.try_pubkey().unwrap(),
+                &signer.try_pubkey().unwrap(),
+                option_index as u8,
+                proposal_tx_indexes[option_index],
+                0,
+                instructions.clone(),
+            );
 
-    for (index, transaction) in data.transactions.iter().enumerate() {
-        let address = Pubkey::from_str(&transaction.address).unwrap();
-        let mut instruction: Instruction = bincode::deserialize(&transaction.instruction).unwrap();
+            let insert_size = batch::transaction_size(&signer.try_pubkey().unwrap(), &[insert]);
 
-        instruction.accounts.get_mut(6).unwrap().is_signer = false;
-        instruction.accounts.get_mut(7).unwrap().is_signer = false;
+            if insert_size > PACKET_DATA_SIZE {
+                error!(
+                "Group of {} actions ({}) doesn't fit into one insert transaction, it's {} bytes, limit is {}, split it into smaller ones",
+                group.len(),
+                label,
+                insert_size,
+                PACKET_DATA_SIZE
+            );
+                oversized_transactions += 1;
+                continue;
+            }
 
-        let execute_instruction = execute_transaction(
-            &governance_program,
-            &governance_key,
-            &proposal_key,
-            &address,
-            &instruction.program_id,
-            &instruction.accounts,
+            let inserted = existing_transactions[option_index]
+                .iter()
+                .position(|(_, transaction)| transaction.instructions == instructions);
+
+            if let Some(position) = inserted {
+                let (transaction_address, _) = existing_transactions[option_index].remove(position);
+
+                info!("Transaction for {} is already inserted, skipping it", label);
+
+                proposal_transactions.push(ProposalTransaction {
+                    address: transaction_address.to_string(),
+                    option_index: option_index as u8,
+                    instructions: instructions_bytes,
+                });
+            } else {
+                grants_to_insert.push((
+                    option_index as u8,
+                    label,
+                    instructions_bytes,
+                    instructions,
+                ));
+            }
+        }
+    }
+
+    if oversized_transactions > 0 {
+        error!(
+            "{} proposal transaction(s) don't fit into transaction size limit, nothing was sent",
+            oversized_transactions
         );
+        return;
+    }
 
-        info!(
-            "Executing proposal transaction, {} of {}...",
-            index + 1,
-            number_of_transactions
+    for (transaction_address, _) in existing_transactions.iter().flatten() {
+        warn!(
+            "Proposal transaction {} is not in the instructions file",
+            transaction_address
         );
-        let tx_sent = send_tx_with_retry(client, &*signer, execute_instruction);
+    }
 
-        if !tx_sent {
-            error_happen = true;
+    info!("Simulating proposal transactions...");
 
-            erroneous_transactions.push(transaction.clone());
-        }
+    let mut simulations = Vec::new();
+
+    // When proposal doesn't exist yet every insert is simulated right after proposal creation
+    // in the same transaction. Description is skipped to fit transaction size limit.
+    let mut preflight_instructions = Vec::new();
+
+    if let Some(creation_instructions) = &creation_instructions {
+        simulations.push((
+            "Create proposal".to_string(),
+            simulation::simulate(client, &signer.try_pubkey().unwrap(), creation_instructions),
+        ));
+
+        preflight_instructions.push(create_proposal_instruction(
+            &governance_program,
+            &governance_key,
+            &proposal_owner_record,
+            &signer.try_pubkey().unwrap(),
+            &signer.try_pubkey().unwrap(),
+            None,
+            &governance_data.realm,
+            data.name.clone(),
+            String::new(),
+            &council_mint,
+            data.vote_type(),
+            option_labels.clone(),
+            use_deny_option,
+            governance_data.proposals_count,
+        ));
+    }
+
+    for (index, (option_index, label, _, instructions)) in grants_to_insert.iter().enumerate() {
+        let insert_instruction = insert_transaction(
+            &governance_program,
+            &governance_key,
+            &proposal_address,
+            &proposal_owner_record,
+            &signer.try_pubkey().unwrap(),
+            &signer.try_pubkey().unwrap(),
+            *option_index,
+            proposal_tx_indexes[*option_index as usize],
+            0,
+            instructions.clone(),
+        );
+
+        let mut simulated_instructions = preflight_instructions.clone();
+        simulated_instructions.push(insert_instruction);
+
+        simulations.push((
+            format!("Insert transaction {} ({})", index + 1, label),
+            simulation::simulate(
+                client,
+                &signer.try_pubkey().unwrap(),
+                &simulated_instructions,
+            ),
+        ));
+    }
+
+    let failed_simulations = simulations
+        .iter()
+        .filter(|(_, result)| !result.is_ok())
+        .count();
+
+    for (label, result) in simulations.iter() {
+        if dry_run || !result.is_ok() {
+            simulation::log_simulation(label, result);
+        }
+    }
+
+    if failed_simulations > 0 {
+        error!(
+            "{} of {} transactions would fail, nothing was sent",
+            failed_simulations,
+            simulations.len()
+        );
+        return;
+    }
+
+    if dry_run {
+        info!("All transactions simulated successfully, nothing was sent");
+        return;
+    }
+
+    if let Some(mut creation_instructions) = creation_instructions {
+        let is_created = |proposal_address: &Pubkey| {
+            proposal::load_proposal(client, proposal_address)
+                .map(|created| {
+                    created.state == ProposalState::Draft
+                        && created.token_owner_record == proposal_owner_record
+                        && created.name == data.name
+                })
+                .unwrap_or(false)
+        };
+
+        let mut proposals_count = governance_data.proposals_count;
+        let mut attempts = 0;
+
+        loop {
+            // creation reported as failed still could land, then proposals count moved because of us
+            if attempts > 0 && is_created(&proposal_address) {
+                info!("Proposal was created");
+                break;
+            }
+
+            // someone else could have created a proposal on the governance since it was read,
+            // then our index is taken and the creation is rebuilt with the next one
+            let governance_bytes = client.get_account_data(&governance_key).unwrap();
+            let current_count = GovernanceV2::deserialize(&mut governance_bytes.as_ref())
+                .unwrap()
+                .proposals_count;
+
+            if current_count != proposals_count {
+                warn!(
+                    "Proposals count of the governance moved from {} to {}, rebuilding proposal creation",
+                    proposals_count, current_count
+                );
+
+                proposals_count = current_count;
+                (proposal_address, creation_instructions) = proposal_creation(proposals_count);
+            }
+
+            let blockhash = client.get_latest_blockhash().unwrap();
+
+            let tx = Transaction::new_signed_with_payer(
+                &creation_instructions,
+                Some(&signer.try_pubkey().unwrap()),
+                &[signer],
+                blockhash,
+            );
+
+            match client.send_and_confirm_transaction(&tx) {
+                Ok(signature) => {
+                    info!("Proposal was created: {:?}", signature);
+                    break;
+                }
+                Err(e) => {
+                    attempts += 1;
+
+                    if attempts >= RETRIES {
+                        error!("Failed to create proposal: {:?}", e);
+                        return;
+                    }
+
+                    warn!("Failed to create proposal: {:?}, retrying...", e);
+                }
+            }
+        }
+
+        // inserts go to the derived address, so it must be the proposal we've just created
+        if !is_created(&proposal_address) {
+            error!(
+                "Proposal {} is not the one we've created, nothing was inserted. Run create-proposal again to continue",
+                proposal_address
+            );
+            return;
+        }
+
+        info!("Proposal address: {}", proposal_address);
+    }
+
+    let mut error_happen = false;
+
+    let mut erroneous_transactions = Vec::new();
+
+    let mut grants_to_insert: VecDeque<_> = grants_to_insert.into();
+
+    while !grants_to_insert.is_empty() {
+        // as many inserts as fit are sent in one transaction, each takes the next index of its option
+        let (batch, insert_instructions) = batch::take_batch(
+            &signer.try_pubkey().unwrap(),
+            &mut grants_to_insert,
+            |(option_index, _, _, instructions), taken| {
+                let taken_in_option = taken
+                    .iter()
+                    .filter(|(taken_option, _, _, _)| taken_option == option_index)
+                    .count();
+
+                insert_transaction(
+                    &governance_program,
+                    &governance_key,
+                    &proposal_address,
+                    &proposal_owner_record,
+                    &signer.try_pubkey().unwrap(),
+                    &signer.try_pubkey().unwrap(),
+                    *option_index,
+                    proposal_tx_indexes[*option_index as usize] + taken_in_option as u16,
+                    0,
+                    instructions.clone(),
+                )
+            },
+        );
+
+        let mut next_indexes = proposal_tx_indexes.clone();
+
+        let transaction_addresses: Vec<Pubkey> = batch
+            .iter()
+            .map(|(option_index, _, _, _)| {
+                let index = next_indexes[*option_index as usize];
+                next_indexes[*option_index as usize] += 1;
+
+                get_proposal_transaction_address(
+                    &governance_program,
+                    &proposal_address,
+                    &option_index.to_le_bytes(),
+                    &index.to_le_bytes(),
+                )
+            })
+            .collect();
+
+        info!(
+            "Adding {} new transaction(s) to the proposal...",
+            batch.len()
+        );
+        // transaction reported as failed still could land, so its accounts are checked as well,
+        // inserts of one transaction land all together
+        let tx_sent = send_tx_with_retry(client, signer, &insert_instructions)
+            || proposal::is_transaction_inserted(client, &transaction_addresses[0], &batch[0].3);
+
+        for ((option_index, _, instructions_bytes, _), transaction_address) in
+            batch.iter().zip(transaction_addresses)
+        {
+            if tx_sent {
+                proposal_transactions.push(ProposalTransaction {
+                    address: transaction_address.to_string(),
+                    option_index: *option_index,
+                    instructions: instructions_bytes.clone(),
+                });
+            } else {
+                error_happen = true;
+
+                erroneous_transactions.push(ErroneousProposalTransactions {
+                    governance_program: governance_program.to_string(),
+                    governance_key: governance_key.to_string(),
+                    proposal_address: proposal_address.to_string(),
+                    proposal_owner_record: proposal_owner_record.to_string(),
+                    governance_authority: signer.try_pubkey().unwrap().to_string(),
+                    option_index: *option_index,
+                    instructions: instructions_bytes.clone(),
+                });
+            }
+        }
+
+        if tx_sent {
+            proposal_tx_indexes = next_indexes;
+        }
+    }
+
+    let transactions_to_execute = TransactionsToExecute {
+        governance: governance_key.to_string(),
+        proposal: proposal_address.to_string(),
+        transactions: proposal_transactions,
+    };
+
+    let j = serde_json::to_string(&transactions_to_execute).unwrap();
+
+    fs::write("../transaction_to_execute.json", j).unwrap();
+
+    info!("Transactions that should be executed successfully saved");
+
+    if error_happen {
+        let j = serde_json::to_string(&erroneous_transactions).unwrap();
+
+        fs::write("../erroneous_txs.json", j).unwrap();
+
+        warn!("During proposal creation error had happened, thats why proposal WAS NOT signed off");
+        info!("All the erroneous transactions were saved to erroneous_txs.json");
+        info!("You can try to insert it again with `retry` command");
+    } else if draft {
+        info!(
+            "Proposal {} is left in Draft, review it and sign it off with `sign-off` command",
+            proposal_address
+        );
+    } else {
+        sign_off(
+            client,
+            signer,
+            &governance_program,
+            &governance_data.realm,
+            &governance_key,
+            &proposal_address,
+        );
+    }
+}
+
+/// Compares tokens the proposal takes from the treasury, together with open proposals,
+/// with the treasury balance. Proposal being continued is counted only once.
+#[allow(clippy::too_many_arguments)]
+fn check_treasury(
+    client: &RpcClient,
+    treasury: &treasury::Treasury,
+    data: &ProposalData,
+    governance_program: &Pubkey,
+    governance_key: &Pubkey,
+    governing_token_mints: &[Pubkey],
+    proposals_count: u32,
+    existing_proposal: Option<&Pubkey>,
+) -> bool {
+    let balance = match treasury.balance(client) {
+        Ok(balance) => balance,
+        Err(e) => {
+            error!("Treasury check failed: {}", e);
+            return false;
+        }
+    };
+
+    let requested = treasury.requested(&data.options(), data.vote_type);
+
+    let committed = treasury.committed(
+        client,
+        governance_program,
+        governance_key,
+        governing_token_mints,
+        proposals_count,
+        existing_proposal,
+    );
+
+    for (proposal_address, amount) in committed.iter() {
+        info!(
+            "Open proposal {} takes {} from the treasury",
+            proposal_address, amount
+        );
+    }
+
+    let committed_total = committed
+        .iter()
+        .map(|(_, amount)| *amount)
+        .fold(0, u64::saturating_add);
+
+    info!(
+        "Treasury {} balance: {}, this proposal takes: {}, open proposals take: {}",
+        treasury.deposit_token, balance, requested, committed_total
+    );
+
+    let total = requested.saturating_add(committed_total);
+
+    if total > balance {
+        error!("Shortfall of {} tokens", total - balance);
+        return false;
+    }
+
+    true
+}
+
+fn sign_off(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    governance_program: &Pubkey,
+    realm: &Pubkey,
+    governance_key: &Pubkey,
+    proposal_address: &Pubkey,
+) {
+    if !proposal::is_signatory(
+        client,
+        governance_program,
+        proposal_address,
+        &signer.try_pubkey().unwrap(),
+    ) {
+        info!(
+            "{} is not a signatory of proposal {}, it stays in Draft until its signatories sign it off",
+            signer.try_pubkey().unwrap(),
+            proposal_address
+        );
+        return;
+    }
+
+    let sign_off_proposal = sign_off_proposal(
+        governance_program,
+        realm,
+        governance_key,
+        proposal_address,
+        &signer.try_pubkey().unwrap(),
+        None,
+    );
+
+    let blockhash = client.get_latest_blockhash().unwrap();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[sign_off_proposal],
+        Some(&signer.try_pubkey().unwrap()),
+        &[signer],
+        blockhash,
+    );
+    let signature = client.send_and_confirm_transaction(&tx).unwrap();
+
+    info!(
+        "Proposal was signed off: {:?}\nWe are ready for voting",
+        signature
+    );
+}
+
+/// Signs off the proposal after checking that everything from the instructions file is inserted
+fn verify_and_sign_off(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    config: &config::Config,
+    data: &ProposalData,
+    proposal_address: &Pubkey,
+    allow_extra: bool,
+) {
+    let governance_program = config.pubkey("GOVERNANCE_PROGRAM");
+
+    let proposal_data = match proposal::load_proposal(client, proposal_address) {
+        Some(proposal_data) => proposal_data,
+        None => {
+            error!("Proposal {} is not found", proposal_address);
+            return;
+        }
+    };
+
+    if proposal_data.state != ProposalState::Draft
+        && proposal_data.state != ProposalState::SigningOff
+    {
+        error!(
+            "Proposal {} is in {:?} state, it's already signed off",
+            proposal_address, proposal_data.state
+        );
+        return;
+    }
+
+    let options = data.options();
+
+    let (mut problems, unexpected) = verify::verify_transactions(
+        client,
+        &governance_program,
+        proposal_address,
+        &proposal_data,
+        &options,
+    );
+
+    // transactions added by someone else are executed as well, so they are signed off only on purpose
+    for transaction_address in unexpected.iter() {
+        if allow_extra {
+            warn!(
+                "Proposal transaction {} is not in the instructions file, signing off because of --allow-extra",
+                transaction_address
+            );
+        } else {
+            problems.push(format!(
+                "proposal transaction {} is not in the instructions file, use --allow-extra to sign off anyway",
+                transaction_address
+            ));
+        }
+    }
+
+    if !problems.is_empty() {
+        for problem in problems.iter() {
+            error!("{}", problem);
+        }
+
+        error!(
+            "Proposal {} doesn't match the instructions file, it WAS NOT signed off",
+            proposal_address
+        );
+        return;
+    }
+
+    info!(
+        "All the transactions of the instructions file are inserted, SHA-256 of the instructions: {}",
+        hex(&instructions_hash(&options))
+    );
+
+    let governance_bytes = client.get_account_data(&proposal_data.governance).unwrap();
+    let governance_data = GovernanceV2::deserialize(&mut governance_bytes.as_ref()).unwrap();
+
+    sign_off(
+        client,
+        signer,
+        &governance_program,
+        &governance_data.realm,
+        &proposal_data.governance,
+        proposal_address,
+    );
+}
+
+fn retry_insert(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    erroneous_transactions: &[ErroneousProposalTransactions],
+    draft: bool,
+) {
+    let first = match erroneous_transactions.first() {
+        Some(first) => first,
+        None => {
+            info!("There are no transactions to insert");
+            return;
+        }
+    };
+
+    let governance_program = Pubkey::from_str(&first.governance_program).unwrap();
+    let governance_key = Pubkey::from_str(&first.governance_key).unwrap();
+    let proposal_address = Pubkey::from_str(&first.proposal_address).unwrap();
+
+    if first.governance_authority != signer.try_pubkey().unwrap().to_string() {
+        error!(
+            "Transactions were inserted by {}, retry should be signed by the same wallet",
+            first.governance_authority
+        );
+        return;
+    }
+
+    let governance_bytes = client.get_account_data(&governance_key).unwrap();
+    let governance_data = GovernanceV2::deserialize(&mut governance_bytes.as_ref()).unwrap();
+
+    let proposal_data = proposal::load_proposal(client, &proposal_address).unwrap();
+
+    if proposal_data.state != ProposalState::Draft {
+        error!(
+            "Proposal {} is in {:?} state, transactions can be inserted only into Draft",
+            proposal_address, proposal_data.state
+        );
+        return;
+    }
+
+    let mut proposal_transactions = Vec::new();
+
+    let mut remaining_transactions = Vec::new();
+
+    for transaction in erroneous_transactions.iter() {
+        let proposal_owner_record = Pubkey::from_str(&transaction.proposal_owner_record).unwrap();
+
+        // next free index is taken from the proposal, so it's correct even if some inserts landed
+        let inserted_in_option = proposal_transactions
+            .iter()
+            .filter(|inserted: &&ProposalTransaction| {
+                inserted.option_index == transaction.option_index
+            })
+            .count();
+
+        let option = match proposal_data.options.get(transaction.option_index as usize) {
+            Some(option) => option,
+            None => {
+                error!(
+                    "Proposal {} has no option {}, transaction is kept in erroneous_txs.json",
+                    proposal_address, transaction.option_index
+                );
+                remaining_transactions.push(transaction.clone());
+                continue;
+            }
+        };
+
+        let proposal_tx_index = option.transactions_next_index + inserted_in_option as u16;
+
+        let instructions: Vec<InstructionData> = transaction
+            .instructions
+            .iter()
+            .map(|bytes| {
+                let instruction: Instruction = bincode::deserialize(bytes).unwrap();
+                InstructionData::from(instruction)
+            })
+            .collect();
+
+        let insert_instruction = insert_transaction(
+            &governance_program,
+            &governance_key,
+            &proposal_address,
+            &proposal_owner_record,
+            &signer.try_pubkey().unwrap(),
+            &signer.try_pubkey().unwrap(),
+            transaction.option_index,
+            proposal_tx_index,
+            0,
+            instructions.clone(),
+        );
+
+        let transaction_address = get_proposal_transaction_address(
+            &governance_program,
+            &proposal_address,
+            &transaction.option_index.to_le_bytes(),
+            &proposal_tx_index.to_le_bytes(),
+        );
+
+        info!("Inserting transaction to the proposal again...");
+        let tx_sent = send_tx_with_retry(client, signer, &[insert_instruction])
+            || proposal::is_transaction_inserted(client, &transaction_address, &instructions);
+
+        if tx_sent {
+            proposal_transactions.push(ProposalTransaction {
+                address: transaction_address.to_string(),
+                option_index: transaction.option_index,
+                instructions: transaction.instructions.clone(),
+            });
+        } else {
+            remaining_transactions.push(transaction.clone());
+        }
+    }
+
+    let mut transactions_to_execute = fs::read_to_string("../transaction_to_execute.json")
+        .ok()
+        .and_then(|data| serde_json::from_str::<TransactionsToExecute>(&data).ok())
+        .filter(|transactions| transactions.proposal == proposal_address.to_string())
+        .unwrap_or(TransactionsToExecute {
+            governance: governance_key.to_string(),
+            proposal: proposal_address.to_string(),
+            transactions: Vec::new(),
+        });
+
+    transactions_to_execute
+        .transactions
+        .extend(proposal_transactions);
+
+    let j = serde_json::to_string(&transactions_to_execute).unwrap();
+
+    fs::write("../transaction_to_execute.json", j).unwrap();
+
+    info!("Inserted transactions were added to transaction_to_execute.json");
+
+    let j = serde_json::to_string(&remaining_transactions).unwrap();
+
+    fs::write("../erroneous_txs.json", j).unwrap();
+
+    if !remaining_transactions.is_empty() {
+        warn!(
+            "{} transaction(s) failed again, they were saved to erroneous_txs.json, proposal WAS NOT signed off",
+            remaining_transactions.len()
+        );
+    } else if draft {
+        info!(
+            "Proposal {} is left in Draft, review it and sign it off with `sign-off` command",
+            proposal_address
+        );
+    } else {
+        sign_off(
+            client,
+            signer,
+            &governance_program,
+            &governance_data.realm,
+            &governance_key,
+            &proposal_address,
+        );
+    }
+}
+
+fn execute_proposal(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    config: &config::Config,
+    data: &TransactionsToExecute,
+    dry_run: bool,
+    wait: bool,
+) {
+    let governance_program = config.pubkey("GOVERNANCE_PROGRAM");
+    let governance_key = Pubkey::from_str(&data.governance).unwrap();
+    let proposal_key = Pubkey::from_str(&data.proposal).unwrap();
+
+    let transaction_addresses: Vec<Pubkey> = data
+        .transactions
+        .iter()
+        .map(|transaction| Pubkey::from_str(&transaction.address).unwrap())
+        .collect();
+
+    if !wait_until_executable(
+        client,
+        signer,
+        &governance_program,
+        &proposal_key,
+        &transaction_addresses,
+        dry_run,
+        wait,
+    ) {
+        return;
+    }
+
+    let proposal_data = proposal::load_proposal(client, &proposal_key).unwrap();
+
+    // transactions of options that haven't won can't be executed
+    let succeeded_option = |transaction: &ProposalTransaction| {
+        proposal_data
+            .options
+            .get(transaction.option_index as usize)
+            .map(|option| option.vote_result == OptionVoteResult::Succeeded)
+            .unwrap_or(false)
+    };
+
+    for option_index in data
+        .transactions
+        .iter()
+        .filter(|transaction| !succeeded_option(transaction))
+        .map(|transaction| transaction.option_index)
+        .collect::<BTreeSet<_>>()
+    {
+        info!(
+            "Option {} hasn't won the vote, skipping its transactions",
+            option_index
+        );
+    }
+
+    let statuses = proposal::load_transactions(client, &transaction_addresses);
+
+    let transactions: Vec<&ProposalTransaction> = data
+        .transactions
+        .iter()
+        .zip(statuses)
+        .filter(|(transaction, _)| succeeded_option(transaction))
+        .filter(|(_, transaction)| match transaction {
+            Some(transaction) => {
+                transaction.execution_status != TransactionExecutionStatus::Success
+            }
+            None => true,
+        })
+        .map(|(transaction, _)| transaction)
+        .collect();
+
+    let executed_count = data
+        .transactions
+        .iter()
+        .filter(|transaction| succeeded_option(transaction))
+        .count()
+        - transactions.len();
+
+    if executed_count > 0 {
+        info!(
+            "{} transaction(s) are already executed, skipping them",
+            executed_count
+        );
+    }
+
+    let number_of_transactions = transactions.len();
+
+    let mut error_happen = false;
+
+    let mut erroneous_transactions = Vec::new();
+
+    let mut pending: VecDeque<(&ProposalTransaction, Instruction)> = transactions
+        .into_iter()
+        .map(|transaction| {
+            let address = Pubkey::from_str(&transaction.address).unwrap();
+
+            let execute_instruction = execute_instruction(
+                &governance_program,
+                &governance_key,
+                &proposal_key,
+                &address,
+                &transaction.instructions,
+            );
+
+            (transaction, execute_instruction)
+        })
+        .collect();
+
+    let mut foreign_signers: Vec<Pubkey> = pending
+        .iter()
+        .flat_map(|(_, execute_instruction)| execution_signers(execute_instruction))
+        .filter(|account| *account != signer.pubkey())
+        .collect();
+
+    foreign_signers.sort();
+    foreign_signers.dedup();
+
+    if !foreign_signers.is_empty() {
+        error!(
+            "Execution requires signatures of {}, run it with that wallet",
+            foreign_signers
+                .iter()
+                .map(|account| account.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        return;
+    }
+
+    let mut executed = 0;
+
+    while !pending.is_empty() {
+        let payer = signer.try_pubkey().unwrap();
+
+        // as many executions as fit are sent in one transaction
+        let (mut batch, mut execute_instructions) =
+            batch::take_batch(&payer, &mut pending, |(_, execute_instruction), _| {
+                execute_instruction.clone()
+            });
+
+        // and as many of them as the compute limit allows
+        let result = batch::shrink_batch(
+            &mut pending,
+            &mut batch,
+            &mut execute_instructions,
+            |instructions| simulation::simulate(client, &payer, instructions),
+        );
+
+        let first = executed + 1;
+        executed += batch.len();
+
+        if dry_run {
+            simulation::log_simulation(
+                &format!(
+                    "Proposal transactions {}-{} of {}",
+                    first, executed, number_of_transactions
+                ),
+                &result,
+            );
+
+            continue;
+        }
+
+        info!(
+            "Executing proposal transactions, {}-{} of {}...",
+            first, executed, number_of_transactions
+        );
+        let tx_sent = send_tx_with_retry(client, &*signer, &execute_instructions);
+
+        if !tx_sent {
+            error_happen = true;
+
+            erroneous_transactions.extend(
+                batch
+                    .into_iter()
+                    .map(|(transaction, _)| transaction.clone()),
+            );
+        }
     }
 
     if error_happen {
@@ -441,24 +1722,213 @@ fn execute_proposal(client: &RpcClient, signer: &dyn Signer, data: &Transactions
     }
 }
 
-fn execute_withdraw(client: &RpcClient, signer: &dyn Signer, data: &WithdrawInstruction) {
-    let blockhash = client.get_latest_blockhash().unwrap();
+/// Checks that voting on the proposal has succeeded and hold-up time of its not executed
+/// transactions has passed. With `wait` it polls the proposal until that happens.
+/// Vote still open after the voting time of the governance is finalized, except in dry run.
+fn wait_until_executable(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    governance_program: &Pubkey,
+    proposal_key: &Pubkey,
+    transaction_addresses: &[Pubkey],
+    dry_run: bool,
+    wait: bool,
+) -> bool {
+    loop {
+        let proposal_data = match proposal::load_proposal(client, proposal_key) {
+            Some(proposal_data) => proposal_data,
+            None => {
+                error!("Proposal {} is not found", proposal_key);
+                return false;
+            }
+        };
+
+        let reason = match proposal_data.state {
+            ProposalState::Draft | ProposalState::SigningOff => format!(
+                "Proposal {} is in {:?} state, it's not signed off yet",
+                proposal_key, proposal_data.state
+            ),
+            ProposalState::Voting => {
+                let governance_bytes = client.get_account_data(&proposal_data.governance).unwrap();
+                let governance_data =
+                    GovernanceV2::deserialize(&mut governance_bytes.as_ref()).unwrap();
+
+                let voting_ends_at = proposal_data.voting_at.unwrap_or_default()
+                    + governance_data.config.max_voting_time as i64;
+
+                let now = proposal::cluster_now(client);
+
+                if voting_ends_at > now {
+                    format!(
+                        "Voting on proposal {} ends in {} second(s)",
+                        proposal_key,
+                        voting_ends_at - now
+                    )
+                } else {
+                    // proposal stays in Voting after voting time until somebody finalizes the vote
+                    if dry_run {
+                        error!(
+                            "Voting time of proposal {} is over, the vote has to be finalized before execution",
+                            proposal_key
+                        );
+                        return false;
+                    }
+
+                    info!(
+                        "Voting time of proposal {} is over, finalizing the vote...",
+                        proposal_key
+                    );
+
+                    let finalize_vote = finalize_vote(
+                        governance_program,
+                        &governance_data.realm,
+                        &proposal_data.governance,
+                        proposal_key,
+                        &proposal_data.token_owner_record,
+                        &proposal_data.governing_token_mint,
+                        None,
+                    );
+
+                    if !send_tx_with_retry(client, signer, &[finalize_vote]) {
+                        error!("Failed to finalize the vote on proposal {}", proposal_key);
+                        return false;
+                    }
+
+                    continue;
+                }
+            }
+            ProposalState::Succeeded
+            | ProposalState::Executing
+            | ProposalState::ExecutingWithErrors => {
+                let voting_completed_at = proposal_data.voting_completed_at.unwrap_or_default();
+
+                // hold-up time is counted from the end of voting
+                let executable_at = proposal::load_transactions(client, transaction_addresses)
+                    .into_iter()
+                    .flatten()
+                    .filter(|transaction| {
+                        transaction.execution_status != TransactionExecutionStatus::Success
+                    })
+                    .map(|transaction| voting_completed_at + transaction.hold_up_time as i64)
+                    .max();
+
+                let now = proposal::cluster_now(client);
+
+                match executable_at {
+                    Some(executable_at) if executable_at > now => format!(
+                        "Hold-up time of proposal {} transactions ends in {} second(s)",
+                        proposal_key,
+                        executable_at - now
+                    ),
+                    _ => return true,
+                }
+            }
+            state => {
+                error!(
+                    "Proposal {} is in {:?} state, its transactions can't be executed",
+                    proposal_key, state
+                );
+                return false;
+            }
+        };
+
+        if !wait {
+            error!("{}, use --wait to wait for it", reason);
+            return false;
+        }
+
+        info!("{}, waiting...", reason);
+
+        thread::sleep(Duration::from_secs(WAIT_INTERVAL_SECS));
+    }
+}
+
+/// Accounts that have to sign the execution transaction besides the governance PDAs
+fn execution_signers(execute_instruction: &Instruction) -> Vec<Pubkey> {
+    execute_instruction
+        .accounts
+        .iter()
+        .filter(|account| account.is_signer)
+        .map(|account| account.pubkey)
+        .collect()
+}
+
+/// Instruction executing proposal transaction, accounts of all its instructions are passed
+/// together with programs of the instructions after the first one.
+/// Governance program signs for the governance and its native treasury PDAs,
+/// so their signer flags are cleared, whatever the instruction is.
+fn execute_instruction(
+    governance_program: &Pubkey,
+    governance_key: &Pubkey,
+    proposal_key: &Pubkey,
+    transaction_address: &Pubkey,
+    instructions: &[Vec<u8>],
+) -> Instruction {
+    let governance_signers = [
+        *governance_key,
+        get_native_treasury_address(governance_program, governance_key),
+    ];
+
+    let mut program_id = None;
+    let mut accounts: Vec<AccountMeta> = Vec::new();
+
+    for bytes in instructions.iter() {
+        let mut instruction: Instruction = bincode::deserialize(bytes).unwrap();
+
+        for account in instruction.accounts.iter_mut() {
+            if governance_signers.contains(&account.pubkey) {
+                account.is_signer = false;
+            }
+        }
+
+        if *program_id.get_or_insert(instruction.program_id) != instruction.program_id {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(instruction.program_id, false));
+        }
 
-    let instruction: Instruction = bincode::deserialize(&data.instruction).unwrap();
+        for account in instruction.accounts {
+            match accounts.iter_mut().find(|a| a.pubkey == account.pubkey) {
+                Some(existing) => {
+                    existing.is_signer |= account.is_signer;
+                    existing.is_writable |= account.is_writable;
+                }
+                None => accounts.push(account),
+            }
+        }
+    }
 
-    let mint = Pubkey::from_str(&env::var("MINT").unwrap()).unwrap();
+    execute_transaction(
+        governance_program,
+        governance_key,
+        proposal_key,
+        transaction_address,
+        &program_id.unwrap(),
+        &accounts,
+    )
+}
+
+fn execute_withdraw(
+    client: &RpcClient,
+    signer: &dyn Signer,
+    config: &config::Config,
+    data: &WithdrawInstruction,
+) {
+    let mut instructions: VecDeque<Instruction> = data
+        .instructions
+        .iter()
+        .map(|instruction| bincode::deserialize(instruction).unwrap())
+        .collect();
+
+    let mint = config.pubkey("MINT");
 
     let associated_token_address =
         spl_associated_token_account::get_associated_token_address(&signer.pubkey(), &mint);
 
-    let tx = if let Ok(_result) = client.get_token_account_balance(&associated_token_address) {
-        Transaction::new_signed_with_payer(
-            &[instruction],
-            Some(&signer.try_pubkey().unwrap()),
-            &[&*signer],
-            blockhash,
-        )
-    } else {
+    if client
+        .get_token_account_balance(&associated_token_address)
+        .is_err()
+    {
         let create_token_instr =
             spl_associated_token_account::instruction::create_associated_token_account(
                 &signer.pubkey(),
@@ -467,17 +1937,30 @@ fn execute_withdraw(client: &RpcClient, signer: &dyn Signer, data: &WithdrawInst
                 &spl_token::id(),
             );
 
-        Transaction::new_signed_with_payer(
-            &[create_token_instr, instruction],
+        instructions.push_front(create_token_instr);
+    }
+
+    // withdrawals of many deposits may not fit into one transaction
+    while !instructions.is_empty() {
+        let (_, batch) = batch::take_batch(
+            &signer.try_pubkey().unwrap(),
+            &mut instructions,
+            |instruction, _| instruction.clone(),
+        );
+
+        let blockhash = client.get_latest_blockhash().unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &batch,
             Some(&signer.try_pubkey().unwrap()),
             &[&*signer],
             blockhash,
-        )
-    };
+        );
 
-    let signature = client.send_and_confirm_transaction(&tx).unwrap();
+        let signature = client.send_and_confirm_transaction(&tx).unwrap();
 
-    info!("Tokens withdrawn successfully: {:?}", signature);
+        info!("Tokens withdrawn successfully: {:?}", signature);
+    }
 }
 
 fn keypair_or_ledger_of(path: &Path) -> Box<dyn Signer> {
@@ -514,3 +1997,24 @@ fn keypair_or_ledger_of(path: &Path) -> Box<dyn Signer> {
         Box::new(read_keypair_file(path.to_str().unwrap()).unwrap())
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_transactions_with_single_instruction() {
+        let transactions: Vec<ProposalTransaction> = serde_json::from_str(
+            r#"[
+                { "address": "a", "instruction": [1, 2, 3] },
+                { "address": "b", "option_index": 1, "instructions": [[1, 2], [3]] }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(transactions[0].option_index, 0);
+        assert_eq!(transactions[0].instructions, vec![vec![1, 2, 3]]);
+        assert_eq!(transactions[1].option_index, 1);
+        assert_eq!(transactions[1].instructions, vec![vec![1, 2], vec![3]]);
+    }
+}
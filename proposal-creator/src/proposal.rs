@@ -0,0 +1,256 @@
+use borsh::BorshDeserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, sysvar::clock::Clock};
+use spl_governance::state::{
+    enums::ProposalState,
+    proposal::{get_proposal_address, ProposalV2},
+    proposal_transaction::{
+        get_proposal_transaction_address, InstructionData, ProposalTransactionV2,
+    },
+    signatory_record::get_signatory_record_address,
+    token_owner_record::TokenOwnerRecordV2,
+};
+
+/// Max number of accounts `getMultipleAccounts` RPC method returns at once
+pub static MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+pub fn load_proposal(client: &RpcClient, proposal_address: &Pubkey) -> Option<ProposalV2> {
+    let proposal_bytes = client.get_account_data(proposal_address).ok()?;
+
+    ProposalV2::deserialize(&mut proposal_bytes.as_ref()).ok()
+}
+
+/// Loads proposal drafted elsewhere, e.g. in Realms UI, to insert transactions into it.
+/// It has to be a Draft of the governance owned by a token owner record of `authority`,
+/// either as the owner or as the governance delegate.
+pub fn load_draft_to_attach(
+    client: &RpcClient,
+    governance_key: &Pubkey,
+    proposal_address: &Pubkey,
+    authority: &Pubkey,
+) -> Result<ProposalV2, String> {
+    let proposal = load_proposal(client, proposal_address)
+        .ok_or_else(|| format!("{} is not a proposal account", proposal_address))?;
+
+    if proposal.governance != *governance_key {
+        return Err(format!(
+            "it belongs to governance {}, not to {}",
+            proposal.governance, governance_key
+        ));
+    }
+
+    if proposal.state != ProposalState::Draft {
+        return Err(format!(
+            "it's in {:?} state, transactions can be inserted only into Draft",
+            proposal.state
+        ));
+    }
+
+    let record_bytes = client
+        .get_account_data(&proposal.token_owner_record)
+        .map_err(|e| {
+            format!(
+                "failed to load its token owner record {}: {}",
+                proposal.token_owner_record, e
+            )
+        })?;
+    let record = TokenOwnerRecordV2::deserialize(&mut record_bytes.as_ref()).map_err(|e| {
+        format!(
+            "{} is not a token owner record: {}",
+            proposal.token_owner_record, e
+        )
+    })?;
+
+    if record.governing_token_owner != *authority && record.governance_delegate != Some(*authority)
+    {
+        return Err(format!(
+            "it's owned by {}, {} is neither the owner nor the delegate of its token owner record",
+            record.governing_token_owner, authority
+        ));
+    }
+
+    Ok(proposal)
+}
+
+/// Only signatories added to the proposal can sign it off
+pub fn is_signatory(
+    client: &RpcClient,
+    governance_program: &Pubkey,
+    proposal_address: &Pubkey,
+    signatory: &Pubkey,
+) -> bool {
+    let signatory_record =
+        get_signatory_record_address(governance_program, proposal_address, signatory);
+
+    client.get_account(&signatory_record).is_ok()
+}
+
+/// Looks for a proposal created by `proposal_owner_record` with the given name, in any state.
+/// Draft one is the proposal left after an interrupted run. Newest proposals are checked first.
+pub fn find_proposal_by_name(
+    client: &RpcClient,
+    governance_program: &Pubkey,
+    governance_key: &Pubkey,
+    governing_token_mint: &Pubkey,
+    proposals_count: u32,
+    proposal_owner_record: &Pubkey,
+    name: &str,
+) -> Option<(Pubkey, ProposalV2)> {
+    let proposal_addresses: Vec<Pubkey> = (0..proposals_count)
+        .rev()
+        .map(|index| {
+            get_proposal_address(
+                governance_program,
+                governance_key,
+                governing_token_mint,
+                &index.to_le_bytes(),
+            )
+        })
+        .collect();
+
+    for addresses in proposal_addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client.get_multiple_accounts(addresses).unwrap();
+
+        for (address, account) in addresses.iter().zip(accounts) {
+            let proposal = match account
+                .and_then(|account| ProposalV2::deserialize(&mut account.data.as_ref()).ok())
+            {
+                Some(proposal) => proposal,
+                None => continue,
+            };
+
+            if proposal.token_owner_record == *proposal_owner_record && proposal.name == name {
+                return Some((*address, proposal));
+            }
+        }
+    }
+
+    None
+}
+
+/// All the proposals of the governance made with any of `governing_token_mints`
+pub fn load_proposals(
+    client: &RpcClient,
+    governance_program: &Pubkey,
+    governance_key: &Pubkey,
+    governing_token_mints: &[Pubkey],
+    proposals_count: u32,
+) -> Vec<(Pubkey, ProposalV2)> {
+    let mut proposal_addresses: Vec<Pubkey> = Vec::new();
+
+    for index in 0..proposals_count {
+        for mint in governing_token_mints.iter() {
+            proposal_addresses.push(get_proposal_address(
+                governance_program,
+                governance_key,
+                mint,
+                &index.to_le_bytes(),
+            ));
+        }
+    }
+
+    // the same mint may be passed twice, e.g. when council and community mints are the same
+    proposal_addresses.sort();
+    proposal_addresses.dedup();
+
+    let mut proposals = Vec::new();
+
+    for addresses in proposal_addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client.get_multiple_accounts(addresses).unwrap();
+
+        for (address, account) in addresses.iter().zip(accounts) {
+            if let Some(proposal) =
+                account.and_then(|account| ProposalV2::deserialize(&mut account.data.as_ref()).ok())
+            {
+                proposals.push((*address, proposal));
+            }
+        }
+    }
+
+    proposals
+}
+
+/// All the transactions already inserted into the proposal option, ordered by index
+pub fn load_proposal_transactions(
+    client: &RpcClient,
+    governance_program: &Pubkey,
+    proposal_address: &Pubkey,
+    proposal: &ProposalV2,
+    option_index: u8,
+) -> Vec<(Pubkey, ProposalTransactionV2)> {
+    let transactions_next_index = proposal.options[option_index as usize].transactions_next_index;
+
+    let transaction_addresses: Vec<Pubkey> = (0..transactions_next_index)
+        .map(|index| {
+            get_proposal_transaction_address(
+                governance_program,
+                proposal_address,
+                &option_index.to_le_bytes(),
+                &index.to_le_bytes(),
+            )
+        })
+        .collect();
+
+    let mut transactions = Vec::new();
+
+    for addresses in transaction_addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client.get_multiple_accounts(addresses).unwrap();
+
+        for (address, account) in addresses.iter().zip(accounts) {
+            // removed transactions leave gaps in indices
+            if let Some(transaction) = account.and_then(|account| {
+                ProposalTransactionV2::deserialize(&mut account.data.as_ref()).ok()
+            }) {
+                transactions.push((*address, transaction));
+            }
+        }
+    }
+
+    transactions
+}
+
+/// Checks if transaction with given instructions is stored at the address,
+/// used when sending has failed but the transaction could have landed anyway
+pub fn is_transaction_inserted(
+    client: &RpcClient,
+    transaction_address: &Pubkey,
+    instructions: &[InstructionData],
+) -> bool {
+    client
+        .get_account_data(transaction_address)
+        .ok()
+        .and_then(|data| ProposalTransactionV2::deserialize(&mut data.as_ref()).ok())
+        .map(|transaction| transaction.instructions == instructions)
+        .unwrap_or(false)
+}
+
+/// Proposal transactions at the addresses, `None` for the ones not found
+pub fn load_transactions(
+    client: &RpcClient,
+    transaction_addresses: &[Pubkey],
+) -> Vec<Option<ProposalTransactionV2>> {
+    let mut transactions = Vec::new();
+
+    for addresses in transaction_addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let accounts = client.get_multiple_accounts(addresses).unwrap();
+
+        transactions.extend(accounts.into_iter().map(|account| {
+            account.and_then(|account| {
+                ProposalTransactionV2::deserialize(&mut account.data.as_ref()).ok()
+            })
+        }));
+    }
+
+    transactions
+}
+
+/// Cluster time, the one hold-up time is checked against
+pub fn cluster_now(client: &RpcClient) -> i64 {
+    let account = client
+        .get_account(&solana_sdk::sysvar::clock::id())
+        .unwrap();
+
+    let clock: Clock = bincode::deserialize(&account.data).unwrap();
+
+    clock.unix_timestamp
+}
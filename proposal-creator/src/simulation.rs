@@ -0,0 +1,81 @@
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{
+    instruction::Instruction, message::Message, packet::PACKET_DATA_SIZE, pubkey::Pubkey,
+    transaction::Transaction,
+};
+
+use crate::batch;
+
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+}
+
+impl SimulationResult {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Runs instructions as one transaction without sending it.
+/// Transaction isn't signed, so it's safe to use with Ledger, signatures and blockhash aren't checked.
+pub fn simulate(
+    client: &RpcClient,
+    payer: &Pubkey,
+    instructions: &[Instruction],
+) -> SimulationResult {
+    let tx = Transaction::new_unsigned(Message::new(instructions, Some(payer)));
+
+    let tx_size = batch::transaction_size(payer, instructions);
+
+    if tx_size > PACKET_DATA_SIZE {
+        return SimulationResult {
+            error: Some(format!(
+                "transaction is {} bytes, more than {} bytes limit",
+                tx_size, PACKET_DATA_SIZE
+            )),
+            logs: Vec::new(),
+            units_consumed: None,
+        };
+    }
+
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        commitment: Some(client.commitment()),
+        ..RpcSimulateTransactionConfig::default()
+    };
+
+    match client.simulate_transaction_with_config(&tx, config) {
+        Ok(response) => SimulationResult {
+            error: response.value.err.map(|e| e.to_string()),
+            logs: response.value.logs.unwrap_or_default(),
+            units_consumed: response.value.units_consumed,
+        },
+        Err(e) => SimulationResult {
+            error: Some(e.to_string()),
+            logs: Vec::new(),
+            units_consumed: None,
+        },
+    }
+}
+
+pub fn log_simulation(label: &str, result: &SimulationResult) {
+    match &result.error {
+        None => info!(
+            "{}: simulation succeeded, compute units used: {}",
+            label,
+            result
+                .units_consumed
+                .map(|units| units.to_string())
+                .unwrap_or_else(|| "unknown".to_string())
+        ),
+        Some(e) => error!("{}: simulation failed: {}", label, e),
+    }
+
+    for log in result.logs.iter() {
+        info!("    {}", log);
+    }
+}
@@ -0,0 +1,421 @@
+use borsh::BorshDeserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{hash::hashv, instruction::Instruction, program_pack::Pack, pubkey::Pubkey};
+use spl_governance::state::{
+    enums::{ProposalState, TransactionExecutionStatus},
+    proposal::{OptionVoteResult, ProposalOption, VoteType},
+};
+use spl_token::instruction::TokenInstruction;
+
+use proposal_actions::{OptionActions, ProposalVoteType};
+
+use crate::proposal;
+
+/// Arguments of voter-stake-registry `grant` instruction, as they go after the discriminator
+#[derive(BorshDeserialize, Debug)]
+struct GrantArgs {
+    _voter_bump: u8,
+    _voter_weight_record_bump: u8,
+    _kind: u8,
+    _start_ts: Option<u64>,
+    _periods: u32,
+    _allow_clawback: bool,
+    amount: u64,
+}
+
+pub struct Treasury {
+    pub voter_stake_program: Pubkey,
+    pub deposit_token: Pubkey,
+    pub mint: Pubkey,
+}
+
+impl Treasury {
+    /// Tokens the instruction takes from `deposit_token`: voter-stake-registry grants
+    /// and SPL token transfers, other instructions don't take anything
+    pub fn outflow(&self, instruction: &Instruction) -> u64 {
+        if instruction.program_id == self.voter_stake_program {
+            let discriminator = &hashv(&[b"global:grant"]).to_bytes()[..8];
+
+            // grant takes tokens from the deposit token account passed to it
+            let from_treasury = instruction
+                .accounts
+                .iter()
+                .any(|account| account.pubkey == self.deposit_token);
+
+            if !from_treasury || !instruction.data.starts_with(discriminator) {
+                return 0;
+            }
+
+            return GrantArgs::deserialize(&mut &instruction.data[8..])
+                .map(|args| args.amount)
+                .unwrap_or(0);
+        }
+
+        if instruction.program_id == spl_token::id() {
+            let from_treasury = instruction
+                .accounts
+                .first()
+                .map(|account| account.pubkey == self.deposit_token)
+                .unwrap_or(false);
+
+            if !from_treasury {
+                return 0;
+            }
+
+            return match TokenInstruction::unpack(&instruction.data) {
+                Ok(TokenInstruction::Transfer { amount }) => amount,
+                Ok(TokenInstruction::TransferChecked { amount, .. }) => amount,
+                _ => 0,
+            };
+        }
+
+        0
+    }
+
+    /// Tokens the proposal takes from `deposit_token` when executed
+    pub fn requested(&self, options: &[OptionActions], vote_type: ProposalVoteType) -> u64 {
+        let option_outflows = options.iter().map(|option| {
+            option
+                .actions
+                .iter()
+                .map(|action| {
+                    let instruction: Instruction =
+                        bincode::deserialize(action.instruction()).unwrap();
+                    self.outflow(&instruction)
+                })
+                .fold(0, u64::saturating_add)
+        });
+
+        may_take(vote_type == ProposalVoteType::SingleChoice, option_outflows)
+    }
+
+    /// Balance of `deposit_token`, fails if it's not a token account of `mint`
+    pub fn balance(&self, client: &RpcClient) -> Result<u64, String> {
+        let data = client
+            .get_account_data(&self.deposit_token)
+            .map_err(|e| format!("failed to load {}: {}", self.deposit_token, e))?;
+
+        let account = spl_token::state::Account::unpack(&data)
+            .map_err(|e| format!("{} is not a token account: {}", self.deposit_token, e))?;
+
+        if account.mint != self.mint {
+            return Err(format!(
+                "{} holds tokens of mint {}, not {}",
+                self.deposit_token, account.mint, self.mint
+            ));
+        }
+
+        Ok(account.amount)
+    }
+
+    /// Tokens to be taken from `deposit_token` by not yet executed transactions of the open proposals,
+    /// defeated options aren't counted. `skip` proposal is not counted
+    pub fn committed(
+        &self,
+        client: &RpcClient,
+        governance_program: &Pubkey,
+        governance_key: &Pubkey,
+        governing_token_mints: &[Pubkey],
+        proposals_count: u32,
+        skip: Option<&Pubkey>,
+    ) -> Vec<(Pubkey, u64)> {
+        let proposals = proposal::load_proposals(
+            client,
+            governance_program,
+            governance_key,
+            governing_token_mints,
+            proposals_count,
+        );
+
+        let mut committed = Vec::new();
+
+        for (address, proposal_data) in proposals.iter() {
+            let open = matches!(
+                proposal_data.state,
+                ProposalState::Draft
+                    | ProposalState::SigningOff
+                    | ProposalState::Voting
+                    | ProposalState::Succeeded
+                    | ProposalState::Executing
+                    | ProposalState::ExecutingWithErrors
+            );
+
+            if !open || Some(address) == skip {
+                continue;
+            }
+
+            let mut option_outflows = Vec::new();
+
+            for (option_index, option) in proposal_data.options.iter().enumerate() {
+                if !is_pending(option) {
+                    continue;
+                }
+
+                let transactions = proposal::load_proposal_transactions(
+                    client,
+                    governance_program,
+                    address,
+                    proposal_data,
+                    option_index as u8,
+                );
+
+                let mut amount: u64 = 0;
+
+                for (_, transaction) in transactions.iter() {
+                    if transaction.execution_status == TransactionExecutionStatus::Success {
+                        continue;
+                    }
+
+                    for instruction in transaction.instructions.iter() {
+                        amount =
+                            amount.saturating_add(self.outflow(&Instruction::from(instruction)));
+                    }
+                }
+
+                option_outflows.push(amount);
+            }
+
+            let amount = may_take(
+                matches!(proposal_data.vote_type, VoteType::SingleChoice),
+                option_outflows.into_iter(),
+            );
+
+            if amount > 0 {
+                committed.push((*address, amount));
+            }
+        }
+
+        committed
+    }
+}
+
+/// Option that may still take tokens: not defeated and with transactions left to execute
+fn is_pending(option: &ProposalOption) -> bool {
+    option.vote_result != OptionVoteResult::Defeated
+        && option.transactions_executed_count < option.transactions_count
+}
+
+/// Tokens options may take all together: only one option wins with single choice,
+/// with multi choice all of them may
+fn may_take(single_choice: bool, option_outflows: impl Iterator<Item = u64>) -> u64 {
+    if single_choice {
+        option_outflows.max().unwrap_or_default()
+    } else {
+        option_outflows.fold(0, u64::saturating_add)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use solana_sdk::instruction::AccountMeta;
+
+    use proposal_actions::{ActionInstruction, GrantInstruction, GrantType, RawInstruction};
+
+    fn treasury() -> Treasury {
+        Treasury {
+            voter_stake_program: Pubkey::new_unique(),
+            deposit_token: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+        }
+    }
+
+    /// voter-stake-registry `grant` taking `amount` from `deposit_token`
+    fn grant(treasury: &Treasury, deposit_token: &Pubkey, amount: u64) -> Instruction {
+        let mut data = hashv(&[b"global:grant"]).to_bytes()[..8].to_vec();
+        data.extend_from_slice(&[255, 254, 2]);
+        data.push(1);
+        data.extend_from_slice(&1_767_225_600u64.to_le_bytes());
+        data.extend_from_slice(&12u32.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&amount.to_le_bytes());
+
+        // registrar, voter, voter_authority, voter_weight_record, vault, deposit_token, ...
+        let mut accounts: Vec<_> = (0..14)
+            .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+            .collect();
+        accounts[5] = AccountMeta::new(*deposit_token, false);
+
+        Instruction::new_with_bytes(treasury.voter_stake_program, &data, accounts)
+    }
+
+    fn transfer(source: &Pubkey, amount: u64) -> Instruction {
+        spl_token::instruction::transfer(
+            &spl_token::id(),
+            source,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &[],
+            amount,
+        )
+        .unwrap()
+    }
+
+    fn grant_action(instruction: &Instruction) -> ActionInstruction {
+        ActionInstruction::Grant(GrantInstruction {
+            wallet: Pubkey::new_unique().to_string(),
+            grant_type: GrantType::Monthly,
+            start: None,
+            periods: 12,
+            allow_clawback: true,
+            amount: 0,
+            group: None,
+            instruction: bincode::serialize(instruction).unwrap(),
+        })
+    }
+
+    fn raw_action(instruction: &Instruction) -> ActionInstruction {
+        ActionInstruction::RawInstruction(RawInstruction {
+            program_id: instruction.program_id.to_string(),
+            group: None,
+            instruction: bincode::serialize(instruction).unwrap(),
+        })
+    }
+
+    #[test]
+    fn counts_grants_from_deposit_token() {
+        let treasury = treasury();
+
+        assert_eq!(
+            treasury.outflow(&grant(&treasury, &treasury.deposit_token, 1_000)),
+            1_000
+        );
+        assert_eq!(
+            treasury.outflow(&grant(&treasury, &Pubkey::new_unique(), 1_000)),
+            0
+        );
+
+        let mut other_instruction = grant(&treasury, &treasury.deposit_token, 1_000);
+        other_instruction.data[0] ^= 1;
+        assert_eq!(treasury.outflow(&other_instruction), 0);
+
+        let mut other_program = grant(&treasury, &treasury.deposit_token, 1_000);
+        other_program.program_id = Pubkey::new_unique();
+        assert_eq!(treasury.outflow(&other_program), 0);
+    }
+
+    #[test]
+    fn counts_transfers_from_deposit_token() {
+        let treasury = treasury();
+
+        assert_eq!(
+            treasury.outflow(&transfer(&treasury.deposit_token, 500)),
+            500
+        );
+        assert_eq!(treasury.outflow(&transfer(&Pubkey::new_unique(), 500)), 0);
+
+        let transfer_checked = spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            &treasury.deposit_token,
+            &treasury.mint,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &[],
+            700,
+            6,
+        )
+        .unwrap();
+        assert_eq!(treasury.outflow(&transfer_checked), 700);
+
+        let approve = spl_token::instruction::approve(
+            &spl_token::id(),
+            &treasury.deposit_token,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &[],
+            900,
+        )
+        .unwrap();
+        assert_eq!(treasury.outflow(&approve), 0);
+    }
+
+    fn option(actions: Vec<ActionInstruction>) -> OptionActions {
+        OptionActions {
+            label: "Option".to_string(),
+            actions,
+        }
+    }
+
+    #[test]
+    fn requests_all_the_actions() {
+        let treasury = treasury();
+        let deposit_token = treasury.deposit_token;
+
+        let options = [option(vec![
+            grant_action(&grant(&treasury, &deposit_token, 1_000)),
+            grant_action(&grant(&treasury, &deposit_token, 1_200)),
+            grant_action(&grant(&treasury, &Pubkey::new_unique(), 5_000)),
+            raw_action(&transfer(&deposit_token, 300)),
+        ])];
+
+        assert_eq!(
+            treasury.requested(&options, ProposalVoteType::SingleChoice),
+            2_500
+        );
+        assert_eq!(treasury.requested(&[], ProposalVoteType::SingleChoice), 0);
+    }
+
+    #[test]
+    fn requests_the_options_that_may_win() {
+        let treasury = treasury();
+        let deposit_token = treasury.deposit_token;
+
+        let options = [
+            option(vec![grant_action(&grant(&treasury, &deposit_token, 1_000))]),
+            option(vec![
+                grant_action(&grant(&treasury, &deposit_token, 700)),
+                raw_action(&transfer(&deposit_token, 600)),
+            ]),
+            option(vec![]),
+        ];
+
+        assert_eq!(
+            treasury.requested(&options, ProposalVoteType::SingleChoice),
+            1_300
+        );
+        assert_eq!(
+            treasury.requested(&options, ProposalVoteType::MultiChoice),
+            2_300
+        );
+
+        let max_grant = grant_action(&grant(&treasury, &deposit_token, u64::MAX));
+        let huge = [
+            option(vec![max_grant]),
+            option(vec![raw_action(&transfer(&deposit_token, 1))]),
+        ];
+
+        assert_eq!(
+            treasury.requested(&huge, ProposalVoteType::MultiChoice),
+            u64::MAX
+        );
+    }
+
+    fn pending(vote_result: OptionVoteResult, executed: u16, count: u16) -> bool {
+        is_pending(&ProposalOption {
+            label: "Option".to_string(),
+            vote_weight: 0,
+            vote_result,
+            transactions_executed_count: executed,
+            transactions_count: count,
+            transactions_next_index: count,
+        })
+    }
+
+    #[test]
+    fn skips_defeated_and_executed_options() {
+        assert!(pending(OptionVoteResult::None, 0, 2));
+        assert!(pending(OptionVoteResult::Succeeded, 1, 2));
+        assert!(!pending(OptionVoteResult::Defeated, 0, 2));
+        assert!(!pending(OptionVoteResult::Succeeded, 2, 2));
+        assert!(!pending(OptionVoteResult::None, 0, 0));
+    }
+
+    #[test]
+    fn takes_the_largest_option_with_single_choice() {
+        assert_eq!(may_take(true, [300, 700, 500].into_iter()), 700);
+        assert_eq!(may_take(false, [300, 700, 500].into_iter()), 1_500);
+        assert_eq!(may_take(false, [u64::MAX, 1].into_iter()), u64::MAX);
+        assert_eq!(may_take(true, std::iter::empty()), 0);
+    }
+}
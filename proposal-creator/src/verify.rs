@@ -0,0 +1,94 @@
+use proposal_actions::{group_actions, hex, instructions_hash, OptionActions};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use spl_governance::state::{proposal::ProposalV2, proposal_transaction::InstructionData};
+
+use crate::proposal;
+
+/// Compares transactions inserted into the proposal with the ones of the instructions file.
+/// Every expected transaction has to be inserted under its option, transactions that aren't
+/// in the file are returned separately, e.g. added to a proposal drafted by someone else.
+pub fn verify_transactions(
+    client: &RpcClient,
+    governance_program: &Pubkey,
+    proposal_address: &Pubkey,
+    proposal_data: &ProposalV2,
+    options: &[OptionActions],
+) -> (Vec<String>, Vec<Pubkey>) {
+    let mut problems = Vec::new();
+    let mut unexpected = Vec::new();
+
+    let labels: Vec<&String> = proposal_data
+        .options
+        .iter()
+        .map(|option| &option.label)
+        .collect();
+
+    if labels
+        != options
+            .iter()
+            .map(|option| &option.label)
+            .collect::<Vec<_>>()
+    {
+        problems.push(format!("proposal has different options: {:?}", labels));
+        return (problems, unexpected);
+    }
+
+    for (option_index, option) in options.iter().enumerate() {
+        let mut inserted = proposal::load_proposal_transactions(
+            client,
+            governance_program,
+            proposal_address,
+            proposal_data,
+            option_index as u8,
+        );
+
+        for group in group_actions(&option.actions) {
+            let instructions: Vec<InstructionData> = group
+                .iter()
+                .map(|action| {
+                    let instruction: Instruction =
+                        bincode::deserialize(action.instruction()).unwrap();
+                    InstructionData::from(instruction)
+                })
+                .collect();
+
+            let position = inserted
+                .iter()
+                .position(|(_, transaction)| transaction.instructions == instructions);
+
+            match position {
+                Some(position) => {
+                    inserted.remove(position);
+                }
+                None => problems.push(format!(
+                    "transaction for {} is not inserted into option \"{}\"",
+                    group
+                        .iter()
+                        .map(|action| action.label())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    option.label
+                )),
+            }
+        }
+
+        unexpected.extend(inserted.into_iter().map(|(address, _)| address));
+    }
+
+    // generated description carries the hash, it has to be the hash of these instructions
+    let hash = hex(&instructions_hash(options));
+
+    if proposal_data
+        .description_link
+        .contains("SHA-256 of the instructions")
+        && !proposal_data.description_link.contains(&hash)
+    {
+        problems.push(format!(
+            "description has a hash of other instructions, expected {}",
+            hash
+        ));
+    }
+
+    (problems, unexpected)
+}
@@ -2,7 +2,7 @@
 
 export $(xargs < .env)
 
-while getopts c:w:g:n:d:a: flag
+while getopts c:w:g:n:d:a:l:p:u:P:S:sbAjfNrWGDX flag
 do
     case "${flag}" in
         c) command=${OPTARG};;
@@ -11,6 +11,22 @@ do
         n) node=${OPTARG};;
         d) deposit=${OPTARG};;
         a) amount=${OPTARG};;
+        l) grantees=${OPTARG};;
+        s) dry_run=--dry-run;;
+        b) group_by_wallet=--group-by-wallet;;
+        A) withdraw_all=--all;;
+        j) json=--json;;
+        f) force=--force;;
+        N) allow_duplicate_name=--allow-duplicate-name;;
+        r) skip_registrar_check=--skip-registrar-check;;
+        W) wait=--wait;;
+        p) profile="--profile ${OPTARG}";;
+        G) generate_description=--generate-description;;
+        u) description_link="--description-link ${OPTARG}";;
+        P) proposal="--proposal ${OPTARG}";;
+        S) signatory="--signatory ${OPTARG}";;
+        D) draft=--draft;;
+        X) allow_extra=--allow-extra;;
 
     esac
 done
@@ -25,13 +41,43 @@ cd ../
 
 if [ $command == "create-proposal" ]
 then
-    cd instruction-generator && cargo r -- -w $wallet grant -g $grants && cd ../proposal-creator && cargo r -- -w $wallet -n $node create-proposal -i ../instructions.json && cd ../
+    cd instruction-generator && cargo r -- $profile -w $wallet -n $node grant -g $grants $group_by_wallet $skip_registrar_check $generate_description $description_link && cd ../proposal-creator && cargo r -- $profile -w $wallet -n $node create-proposal -i ../instructions.json $proposal $signatory $dry_run $force $allow_duplicate_name $draft && cd ../
+elif [ $command == "validate" ]
+then
+    cd instruction-generator && cargo r -- $profile validate -g $grants && cd ../
+elif [ $command == "preview" ]
+then
+    cd instruction-generator && cargo r -- $profile preview -g $grants && cd ../
+elif [ $command == "clawback" ]
+then
+    cd instruction-generator && cargo r -- $profile -n $node clawback $grantees && cd ../proposal-creator && cargo r -- $profile -w $wallet -n $node create-proposal -i ../clawback_instructions.json $dry_run $force $allow_duplicate_name && cd ../
+elif [ $command == "inspect-voter" ]
+then
+    cd instruction-generator && cargo r -- $profile -n $node inspect-voter $grantees $json && cd ../
+elif [ $command == "discover" ]
+then
+    cd proposal-creator && cargo r -- $profile -n $node discover && cd ../
+elif [ $command == "retry" ]
+then
+    cd proposal-creator && cargo r -- $profile -w $wallet -n $node retry -e ../erroneous_txs.json $draft && cd ../
+elif [ $command == "sign-off" ]
+then
+    cd proposal-creator && cargo r -- $profile -w $wallet -n $node sign-off -i ../instructions.json $proposal $allow_extra && cd ../
+elif [ $command == "hash" ]
+then
+    cd proposal-creator && cargo r -- hash -i ../instructions.json && cd ../
 elif [ $command == "execute" ]
 then
-    cd proposal-creator && cargo r -- -w $wallet -n $node execute -t ../transaction_to_execute.json && cd ../
+    cd proposal-creator && cargo r -- $profile -w $wallet -n $node execute -t ../transaction_to_execute.json $dry_run $wait && cd ../
 elif [ $command == "withdraw" ]
 then
-    cd instruction-generator && cargo r -- -w $wallet withdraw -d $deposit -a $amount && cd ../proposal-creator && cargo r -- -w $wallet -n $node execute-withdraw -i ../withdraw.json && cd ../
+    if [ -n "$withdraw_all" ]
+    then
+        withdraw_args=$withdraw_all
+    else
+        withdraw_args="-d $deposit -a $amount"
+    fi
+    cd instruction-generator && cargo r -- $profile -w $wallet -n $node withdraw $withdraw_args && cd ../proposal-creator && cargo r -- $profile -w $wallet -n $node execute-withdraw -i ../withdraw.json && cd ../
 else
     echo "Unknow command"
 fi